@@ -0,0 +1,88 @@
+/*
+ * SPDX-FileCopyrightText: 2025 UnionTech Software Technology Co., Ltd.
+ *
+ * SPDX-License-Identifier: GPL-2.0-or-later
+ */
+
+use anyhow::Result;
+use log::LevelFilter;
+use utzip::cli::{self, UnzipArgs};
+use utzip::error::{self, ZipError};
+use utzip::unzip::{ExtractOptions, OverwritePolicy, ZipExtractor};
+use utzip::utils::log::LogConfig;
+use utzip::zip::ZipArchive;
+
+fn main() {
+    let args = cli::parse_args_unzip();
+    if args.version {
+        cli::show_version_unzip();
+        return;
+    }
+
+    LogConfig::init_logger(args.quiet, false, LevelFilter::Warn);
+    match run(&args) {
+        Ok(true) => {}
+        Ok(false) => std::process::exit(error::PK_ERR),
+        Err(e) => {
+            eprintln!("utunzip error: {:#}", e);
+            std::process::exit(error::unzip_exit_code(&e));
+        }
+    }
+}
+
+// 返回false表示部分条目处理失败
+fn run(args: &UnzipArgs) -> Result<bool> {
+    let zip_path = args
+        .zipfile
+        .clone()
+        .ok_or_else(|| ZipError::InvalidArguments("missing zipfile".to_string()))?;
+    if !zip_path.exists() {
+        return Err(ZipError::ArchiveNotFound(zip_path).into());
+    }
+    let archive = ZipArchive::new(&zip_path.to_string_lossy())?;
+
+    let overwrite = if args.overwrite {
+        OverwritePolicy::Always
+    } else if args.never_overwrite {
+        OverwritePolicy::Never
+    } else if args.rename {
+        OverwritePolicy::Rename
+    } else {
+        OverwritePolicy::Prompt
+    };
+    let options = ExtractOptions {
+        dest: args.exdir.clone().unwrap_or_else(|| ".".into()),
+        overwrite,
+        include: args.files.clone(),
+        exclude: args.exclude.clone(),
+        no_wildcards: args.no_wildcards,
+        junk_paths: args.junk_paths,
+        password: args.password.clone(),
+        restore_times: !args.no_timestamps,
+        restore_permissions: true,
+    };
+    let mut extractor = ZipExtractor::new(archive, options);
+
+    if args.list {
+        println!("Archive:  {}", zip_path.display());
+        extractor.list()?;
+        return Ok(true);
+    }
+
+    LogConfig::println(&format!("Archive:  {}", zip_path.display()));
+    if args.test {
+        let summary = extractor.test()?;
+        if summary.failed == 0 {
+            LogConfig::println(&format!(
+                "No errors detected in compressed data of {}.",
+                zip_path.display()
+            ));
+        } else {
+            println!("At least one error was detected in {}.", zip_path.display());
+        }
+        return Ok(summary.failed == 0);
+    }
+
+    let summary = extractor.extract()?;
+    Ok(summary.failed == 0)
+}
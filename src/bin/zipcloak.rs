@@ -4,6 +4,49 @@
  * SPDX-License-Identifier: GPL-2.0-or-later
  */
 
+use anyhow::Result;
+use log::LevelFilter;
+use utzip::cli::{self, ZipCloakArgs};
+use utzip::error::{self, ZipCloakError};
+use utzip::utils::common::read_password;
+use utzip::utils::log::LogConfig;
+use utzip::zipcloak::ZipCloak;
+
 fn main() {
-    println!("Hello, world!");
+    let args = cli::parse_args_cloak();
+    if args.version {
+        cli::show_version_cloak();
+        return;
+    }
+
+    LogConfig::init_logger(args.quiet, false, LevelFilter::Warn);
+    if let Err(e) = run(&args) {
+        eprintln!("{:#}", e);
+        std::process::exit(error::exit_code(&e));
+    }
+}
+
+fn run(args: &ZipCloakArgs) -> Result<()> {
+    let cloak = ZipCloak::new(args)?;
+    if cloak.pending_entries()? == 0 {
+        let reason = if args.decrypt {
+            "no encrypted files"
+        } else {
+            "all entries already encrypted"
+        };
+        return Err(ZipCloakError::NothingToDo(reason.to_string()).into());
+    }
+
+    let password = read_password("Enter password: ")?;
+    if password.is_empty() {
+        return Err(ZipCloakError::InvalidArguments(
+            "zero length password not allowed".to_string(),
+        )
+        .into());
+    }
+    // 加密时需要再输入一次确认
+    if !args.decrypt && read_password("Verify password: ")? != password {
+        return Err(ZipCloakError::PasswordMismatch.into());
+    }
+    cloak.run(&password)
 }
@@ -4,6 +4,34 @@
  * SPDX-License-Identifier: GPL-2.0-or-later
  */
 
+use anyhow::Result;
+use log::LevelFilter;
+use std::io::{self, Read};
+use utzip::cli::{self, ZipNoteArgs};
+use utzip::error;
+use utzip::utils::log::LogConfig;
+use utzip::zipnote::ZipNote;
+
 fn main() {
-    println!("Hello, world!");
+    let args = cli::parse_args_note();
+    if args.version {
+        cli::show_version_note();
+        return;
+    }
+
+    LogConfig::init_logger(args.quiet, false, LevelFilter::Warn);
+    if let Err(e) = run(&args) {
+        eprintln!("{:#}", e);
+        std::process::exit(error::exit_code(&e));
+    }
+}
+
+fn run(args: &ZipNoteArgs) -> Result<()> {
+    let note = ZipNote::new(args)?;
+    if !args.write {
+        return note.dump(&mut io::stdout().lock());
+    }
+    let mut input = String::new();
+    io::stdin().read_to_string(&mut input)?;
+    note.update(&input)
 }
@@ -4,6 +4,22 @@
  * SPDX-License-Identifier: GPL-2.0-or-later
  */
 
+use log::LevelFilter;
+use utzip::cli;
+use utzip::error;
+use utzip::utils::log::LogConfig;
+use utzip::zipsplit::ZipSplitter;
+
 fn main() {
-    println!("Hello, world!");
+    let args = cli::parse_args_split();
+    if args.version {
+        cli::show_version_split();
+        return;
+    }
+
+    LogConfig::init_logger(args.quiet, false, LevelFilter::Warn);
+    if let Err(e) = ZipSplitter::new(&args).and_then(|splitter| splitter.run()) {
+        eprintln!("{:#}", e);
+        std::process::exit(error::exit_code(&e));
+    }
 }
@@ -4,6 +4,10 @@
  * SPDX-License-Identifier: GPL-2.0-or-later
  */
 
+use crate::encryption::aes::{AesStrength, AesVendorVersion};
+use crate::encryption::EncryptionMethod;
+use crate::error::{PK_PARAM, ZE_PARMS};
+use crate::zip::{CompressionMethod, SplitConfig};
 use chrono::NaiveDate;
 use clap::{ArgAction, Args, CommandFactory, Parser};
 use std::path::PathBuf;
@@ -135,6 +139,18 @@ pub struct SplitOptions {
     pub split_verbose: bool,
 }
 
+impl SplitOptions {
+    // 指定了 -s 时返回分卷设置
+    pub fn config(&self) -> Option<SplitConfig> {
+        self.split_size.map(|size| SplitConfig {
+            size,
+            pause: self.split_pause,
+            bell: self.split_beep,
+            verbose: self.split_verbose,
+        })
+    }
+}
+
 #[derive(Debug, Clone, Args, Default)]
 #[group(id = "show_files_options")]
 #[command(next_help_heading = "Show files")]
@@ -184,10 +200,43 @@ pub struct CompressionOptions {
 
     /// Set compression method to cm
     #[arg(short = 'Z', long = "compression-method", value_name = "CM",
-        value_parser = clap::builder::PossibleValuesParser::new(["store", "deflate", "bzip2"]))]
+        value_parser = clap::builder::PossibleValuesParser::new(["store", "deflate", "bzip2", "zstd", "lzma", "xz"]))]
     pub compression_method: Option<String>,
 }
 
+impl CompressionOptions {
+    // -0 到 -9 指定的压缩级别，未指定时返回None
+    pub fn level(&self) -> Option<u32> {
+        let levels = [
+            self.store_only,
+            self.compress_faster,
+            self.level_2,
+            self.level_3,
+            self.level_4,
+            self.level_5,
+            self.level_6,
+            self.level_7,
+            self.level_8,
+            self.compress_better,
+        ];
+        levels
+            .iter()
+            .rposition(|&set| set)
+            .map(|level| level as u32)
+    }
+
+    // -Z 指定的压缩方法，-0 等价于store
+    pub fn method(&self) -> CompressionMethod {
+        if self.store_only {
+            return CompressionMethod::Stored;
+        }
+        self.compression_method
+            .as_deref()
+            .and_then(CompressionMethod::from_name)
+            .unwrap_or(CompressionMethod::Deflated)
+    }
+}
+
 #[derive(Debug, Clone, Args, Default)]
 #[group(id = "encryption_options")]
 #[command(next_help_heading = "Encryption")]
@@ -198,6 +247,20 @@ pub struct EncryptionOptions {
     /// Use standard encryption, password is pswd
     #[arg(short = 'P', long = "password")]
     pub password: Option<String>,
+    /// Use WinZip AES-256 encryption instead of standard encryption
+    #[arg(long = "aes256", action = ArgAction::SetTrue)]
+    pub aes256: bool,
+}
+
+impl EncryptionOptions {
+    // 设置密码后使用的加密方式
+    pub fn method(&self) -> EncryptionMethod {
+        if self.aes256 {
+            EncryptionMethod::Aes(AesStrength::Aes256, AesVendorVersion::Ae2)
+        } else {
+            EncryptionMethod::ZipCrypto
+        }
+    }
 }
 
 #[derive(Debug, Clone, Args, Default)]
@@ -535,6 +598,66 @@ pub struct ZipSplitArgs {
     pub license: bool,
 }
 
+#[derive(Debug, Parser, Clone, Default)]
+#[command(name = "utunzip")]
+#[command(about = "Extract, list or test files in a ZIP archive")]
+pub struct UnzipArgs {
+    /// Input zip file
+    #[arg(value_name = "ZIPFILE")]
+    pub zipfile: Option<PathBuf>,
+
+    /// Only process entries matching these patterns
+    #[arg(value_name = "FILES")]
+    pub files: Vec<String>,
+
+    /// Exclude entries matching these patterns
+    #[arg(short = 'x', long = "exclude", value_name = "PATTERN", num_args = 1..)]
+    pub exclude: Vec<String>,
+
+    /// Extract files into exdir
+    #[arg(short = 'd', long = "exdir", value_name = "EXDIR")]
+    pub exdir: Option<PathBuf>,
+
+    /// List archive contents (short format)
+    #[arg(short = 'l', long = "list", action = ArgAction::SetTrue)]
+    pub list: bool,
+    /// Test archive integrity
+    #[arg(short = 't', long = "test", action = ArgAction::SetTrue, conflicts_with = "list")]
+    pub test: bool,
+
+    /// Overwrite existing files without prompting
+    #[arg(short = 'o', long = "overwrite", action = ArgAction::SetTrue)]
+    pub overwrite: bool,
+    /// Never overwrite existing files
+    #[arg(short = 'n', long = "never-overwrite", action = ArgAction::SetTrue, conflicts_with = "overwrite")]
+    pub never_overwrite: bool,
+    /// Extract to a new name when the file already exists
+    #[arg(long = "rename", action = ArgAction::SetTrue, conflicts_with_all = ["overwrite", "never_overwrite"])]
+    pub rename: bool,
+
+    /// Junk paths (do not make directories)
+    #[arg(short = 'j', long = "junk-paths", action = ArgAction::SetTrue)]
+    pub junk_paths: bool,
+    /// Skip restoration of timestamps
+    #[arg(short = 'D', long = "no-timestamps", action = ArgAction::SetTrue)]
+    pub no_timestamps: bool,
+    /// Treat patterns as literal names (no wildcards)
+    #[arg(long = "nw", action = ArgAction::SetTrue)]
+    pub no_wildcards: bool,
+
+    /// Password for encrypted entries
+    #[arg(short = 'P', long = "password", value_name = "PASSWORD")]
+    pub password: Option<String>,
+
+    /// Quiet operation, suppress some informational messages
+    #[arg(short = 'q', long = "quiet", action = ArgAction::SetTrue)]
+    pub quiet: bool,
+
+    /// Show version info
+    #[arg(short = 'v', long = "version", action = ArgAction::SetTrue)]
+    pub version: bool,
+}
+
 #[derive(Debug, Clone, PartialEq, Default)]
 pub enum Command {
     #[default]
@@ -549,7 +672,7 @@ pub enum Command {
 }
 
 pub fn parse_args() -> ZipArgs {
-    let mut args = ZipArgs::parse();
+    let mut args = ZipArgs::try_parse().unwrap_or_else(|e| exit_parse_error(e, ZE_PARMS));
 
     // 如果设置了show_options参数，显示帮助信息并退出
     if args.other.show_options {
@@ -603,19 +726,25 @@ pub fn parse_args() -> ZipArgs {
                 // ZIP文件存在，必须提供 --out 参数
                 if args.other.out.is_none() {
                     eprintln!("zip error: when zip file exists, split option (-s) requires output file (--out)");
-                    std::process::exit(1);
+                    std::process::exit(ZE_PARMS);
                 }
             }
             // ZIP文件不存在时，不要求 --out 参数，可以直接使用原文件名
         } else {
             eprintln!("zip error: split option (-s) requires zip file name");
-            std::process::exit(1);
+            std::process::exit(ZE_PARMS);
         }
     }
 
     args
 }
 
+// 参数错误时与原生zip/unzip一致返回 ZE_PARMS/PK_PARAM，-h/--help 等正常退出
+fn exit_parse_error(error: clap::Error, code: i32) -> ! {
+    let _ = error.print();
+    std::process::exit(if error.use_stderr() { code } else { 0 });
+}
+
 // 解析日期字符串为 NaiveDate 类型, 支持 MMDDYYYY 和 YYYY-MM-DD 格式
 fn parse_date(date_str: &str) -> Result<NaiveDate, String> {
     if date_str.len() == 8 {
@@ -663,19 +792,24 @@ fn parse_split_size_arg(s: &str, min_size: u64) -> Result<u64, String> {
 // 解析zipnote命令行参数
 #[allow(dead_code)]
 pub fn parse_args_note() -> ZipNoteArgs {
-    ZipNoteArgs::parse()
+    ZipNoteArgs::try_parse().unwrap_or_else(|e| exit_parse_error(e, ZE_PARMS))
 }
 
 // 解析zipcloak命令行参数
 #[allow(dead_code)]
 pub fn parse_args_cloak() -> ZipCloakArgs {
-    ZipCloakArgs::parse()
+    ZipCloakArgs::try_parse().unwrap_or_else(|e| exit_parse_error(e, ZE_PARMS))
 }
 
 // 解析zipsplit命令行参数
 #[allow(dead_code)]
 pub fn parse_args_split() -> ZipSplitArgs {
-    ZipSplitArgs::parse()
+    ZipSplitArgs::try_parse().unwrap_or_else(|e| exit_parse_error(e, ZE_PARMS))
+}
+
+// 解析unzip命令行参数
+pub fn parse_args_unzip() -> UnzipArgs {
+    UnzipArgs::try_parse().unwrap_or_else(|e| exit_parse_error(e, PK_PARAM))
 }
 
 #[allow(dead_code)]
@@ -847,12 +981,18 @@ Compression:
   -Z cm     set compression method to cm:
               store   - store without compression, same as option -0
               deflate - original zip deflate, same as -1 to -9 (default)
+              zstd    - use Zstandard compression, -1 to -9 map to
+                        zstd levels (need modern unzip)
+              lzma    - use LZMA compression (need modern unzip)
+              xz      - use XZ compression (need modern unzip)
             if bzip2 is enabled:
               bzip2 - use bzip2 compression (need modern unzip)
 
 Encryption:
   -e        Use standard (weak) PKZip 2.0 encryption, prompt for password
   -P pswd   use standard encryption, password is pswd
+  --aes256  use WinZip AES-256 encryption (AE-2) with -e or -P
+              instead of standard encryption (need modern unzip)
 
 Splits (archives created as a set of split files):
   -s ssize  create split archive with splits of size ssize, where ssize nm
@@ -1058,3 +1198,7 @@ pub fn show_version_split() {
         env!("CARGO_PKG_VERSION")
     );
 }
+
+pub fn show_version_unzip() {
+    println!("UtUnzip {} (built with rustc)", env!("CARGO_PKG_VERSION"));
+}
@@ -0,0 +1,597 @@
+/*
+ * SPDX-FileCopyrightText: 2025 UnionTech Software Technology Co., Ltd.
+ *
+ * SPDX-License-Identifier: GPL-2.0-or-later
+ */
+
+// 默认命令：添加或替换归档中的条目，-u/-f/--FS 时按文件系统中的变化同步归档
+use super::{entry_name, is_stdout, open_archive, report, source_path, zip_display, STDIO_NAME};
+use crate::cli;
+use crate::error::ZipError;
+use crate::utils::common::{
+    datetime_to_dos, filter_filesystem_files, match_pattern, safe_move_file,
+    search_pattern_in_archive, temp_archive_path, RunState,
+};
+use crate::utils::sanitize::sanitize_entry_name;
+use crate::zip::{FileOptions, ZipFile, ZipSink, ZipWriter};
+use anyhow::Result;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+// 写入新归档的一个步骤
+pub(super) enum Step {
+    // 原样复制原归档中的第n个条目
+    Copy(usize),
+    // 从文件系统添加条目，action 为提示信息的前缀
+    Add {
+        name: String,
+        path: PathBuf,
+        action: &'static str,
+    },
+    // 不再写入的条目：-d 匹配的条目，或 --FS 时文件系统中已经没有对应文件的条目
+    Delete(String),
+}
+
+pub fn run(state: &mut RunState) -> Result<()> {
+    let args = state.args.clone();
+    let zip_path = state.zip_file.clone();
+    if let Some(path) = &zip_path {
+        if !is_stdout(Some(path)) && path.exists() {
+            open_archive(state)?;
+        }
+    }
+
+    let files = collect_files(state, &args)?;
+    let steps = plan(state, &files)?;
+    let changed = steps.iter().any(|step| !matches!(step, Step::Copy(_)));
+    if !changed && !args.basic_options.add_archive_comment {
+        return Err(ZipError::NothingToDo(zip_display(state)).into());
+    }
+    write_archive(state, &steps)
+}
+
+// 按步骤写出新归档：写入标准输出或分卷，或者先写入临时文件再替换目标归档
+pub(super) fn write_archive(state: &mut RunState, steps: &[Step]) -> Result<()> {
+    write_archive_with_prefix(state, steps, &[])
+}
+
+// 同 write_archive，prefix 写在归档之前(-A 保留的自解压程序)，只用于写入单个文件
+pub(super) fn write_archive_with_prefix(
+    state: &mut RunState,
+    steps: &[Step],
+    prefix: &[u8],
+) -> Result<()> {
+    let args = state.args.clone();
+    let Some(zip_path) = state.zip_file.clone() else {
+        return Err(ZipError::InvalidArguments("missing zipfile".to_string()).into());
+    };
+    if is_stdout(Some(&zip_path)) {
+        let mut writer = ZipWriter::new_stream(io::stdout());
+        write_entries(state, &mut writer, steps)?;
+        writer.finish()?.into_inner().flush()?;
+        return remove_sources(state, steps);
+    }
+
+    // --out 时保留原归档，写入新的归档
+    let target = args.other.out.clone().unwrap_or_else(|| zip_path.clone());
+    let latest;
+    if let Some(config) = args.split.config() {
+        // 分卷归档直接写入目标位置，-T 在全部分卷写完后检查
+        let mut writer = ZipWriter::new_split(&target.to_string_lossy(), config)?;
+        latest = write_entries(state, &mut writer, steps)?;
+        writer.finish()?;
+        if args.test.test || args.test.test_cmd.is_some() {
+            super::test::test_archive(state, &target)?;
+        }
+    } else {
+        let temp = temp_archive_path(&target, args.other.temp_path.as_deref());
+        state.zip_file_tmp = Some(temp.clone());
+        latest = match write_temp(state, &temp, steps, prefix) {
+            Ok(latest) => latest,
+            Err(e) => {
+                let _ = fs::remove_file(&temp);
+                return Err(e);
+            }
+        };
+        if args.test.test || args.test.test_cmd.is_some() {
+            if let Err(e) = super::test::test_archive(state, &temp) {
+                let _ = fs::remove_file(&temp);
+                return Err(e);
+            }
+        }
+        // 替换原归档时保留其权限
+        if let Ok(metadata) = fs::metadata(&target) {
+            fs::set_permissions(&temp, metadata.permissions())?;
+        }
+        state.archive = None;
+        safe_move_file(&temp, &target)?;
+        state.zip_file_tmp = None;
+    }
+
+    // -o：将归档的修改时间设为最新条目的时间
+    if let (true, Some(latest)) = (args.basic_options.latest_time, latest) {
+        filetime::set_file_mtime(&target, filetime::FileTime::from_system_time(latest))?;
+    }
+    remove_sources(state, steps)
+}
+
+fn write_temp(
+    state: &mut RunState,
+    temp: &Path,
+    steps: &[Step],
+    prefix: &[u8],
+) -> Result<Option<SystemTime>> {
+    let mut writer = ZipWriter::with_prefix(&temp.to_string_lossy(), prefix)?;
+    let latest = write_entries(state, &mut writer, steps)?;
+    writer.finish()?;
+    Ok(latest)
+}
+
+// 收集要添加的文件，返回 条目名称 -> 文件系统路径
+fn collect_files(state: &mut RunState, args: &cli::ZipArgs) -> Result<BTreeMap<String, PathBuf>> {
+    let mut names: Vec<PathBuf> = args.files.clone();
+    if args.basic_options.read_names_from_stdin {
+        for line in io::stdin().lock().lines() {
+            let line = line?;
+            let line = line.trim_end_matches('\r');
+            if !line.is_empty() {
+                names.push(PathBuf::from(line));
+            }
+        }
+    }
+
+    let mut files = BTreeMap::new();
+    if names.is_empty() && args.command == cli::Command::Update {
+        // -u/-f/--FS 没有指定文件时检查归档中的所有条目
+        if let Some(archive) = &state.archive {
+            for index in 0..archive.len() {
+                let name = archive.by_index_raw(index)?.name();
+                if let Ok(local) = sanitize_entry_name(&name) {
+                    if fs::symlink_metadata(&local).is_ok() {
+                        files.insert(name, PathBuf::from(local));
+                    }
+                }
+            }
+        }
+    } else if args.other.recurse_patterns {
+        // -R：从当前目录递归，文件参数作为匹配模式
+        let mut found = BTreeMap::new();
+        walk(Path::new("."), args, &mut found)?;
+        for (name, path) in found {
+            let base = name.trim_end_matches('/').rsplit('/').next().unwrap_or("");
+            let matched = names.iter().any(|pattern| {
+                let pattern = pattern.to_string_lossy();
+                match_pattern(&name, &pattern, args.other.no_wildcards)
+                    || match_pattern(base, &pattern, args.other.no_wildcards)
+            });
+            if matched {
+                files.insert(name, path);
+            }
+        }
+    } else {
+        for path in names {
+            if path.as_os_str() == STDIO_NAME {
+                files.insert(STDIO_NAME.to_string(), path);
+            } else if fs::symlink_metadata(&path).is_ok() {
+                add_path(&path, args, &mut files)?;
+            } else {
+                // 文件系统中不存在时按模式匹配归档中已有的条目
+                let pattern = path.to_string_lossy().to_string();
+                files.extend(search_pattern_in_archive(state, &pattern, args)?);
+            }
+        }
+    }
+
+    // 不能把正在写入的归档本身加入归档
+    let zip_file = state
+        .zip_file
+        .as_ref()
+        .and_then(|path| fs::canonicalize(path).ok());
+    files.retain(|_, path| {
+        let path = source_path(path);
+        zip_file.is_none() || fs::canonicalize(path).ok() != zip_file
+    });
+    // 标准输入没有对应的文件，不参与文件名和日期筛选
+    let stdin = files.remove(STDIO_NAME);
+    let mut files = filter_filesystem_files(&files, args);
+    if let Some(path) = stdin {
+        files.insert(STDIO_NAME.to_string(), path);
+    }
+    Ok(files)
+}
+
+// 添加一个文件参数，目录在 -r 时递归
+fn add_path(path: &Path, args: &cli::ZipArgs, files: &mut BTreeMap<String, PathBuf>) -> Result<()> {
+    if is_dir(path, args) {
+        add_dir_entry(path, args, files);
+        if args.basic_options.recurse {
+            walk(path, args, files)?;
+        }
+    } else {
+        files.insert(
+            entry_name(path, args.basic_options.junk_paths, false),
+            path.to_path_buf(),
+        );
+    }
+    Ok(())
+}
+
+// 递归收集目录下的文件和子目录，按名称排序
+fn walk(dir: &Path, args: &cli::ZipArgs, files: &mut BTreeMap<String, PathBuf>) -> Result<()> {
+    let mut children = fs::read_dir(dir)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<io::Result<Vec<_>>>()?;
+    children.sort();
+    for child in children {
+        if is_dir(&child, args) {
+            add_dir_entry(&child, args, files);
+            walk(&child, args, files)?;
+        } else {
+            let path = child.strip_prefix(".").unwrap_or(&child).to_path_buf();
+            files.insert(
+                entry_name(&path, args.basic_options.junk_paths, false),
+                path,
+            );
+        }
+    }
+    Ok(())
+}
+
+// -y 时符号链接按链接本身存储，不进入链接指向的目录
+fn is_dir(path: &Path, args: &cli::ZipArgs) -> bool {
+    if args.other.store_symlinks {
+        fs::symlink_metadata(path).is_ok_and(|metadata| metadata.is_dir())
+    } else {
+        path.is_dir()
+    }
+}
+
+// -D 不添加目录条目，-j 丢弃路径后目录条目也没有意义
+fn add_dir_entry(path: &Path, args: &cli::ZipArgs, files: &mut BTreeMap<String, PathBuf>) {
+    if args.other.no_dir_entries || args.basic_options.junk_paths {
+        return;
+    }
+    let name = entry_name(path, false, true);
+    if !name.is_empty() {
+        files.insert(name, path.to_path_buf());
+    }
+}
+
+// 决定原归档中每个条目的处理方式：未指定的条目和 -u/-f 时没有变化的条目原样复制
+// -f 不添加新条目，--FS 删除文件系统中已不存在的条目
+fn plan(state: &RunState, files: &BTreeMap<String, PathBuf>) -> Result<Vec<Step>> {
+    let args = &state.args;
+    let update = args.command == cli::Command::Update;
+    let freshen = args.basic_mode_options.freshen;
+    let replace = if update && freshen {
+        "freshening: "
+    } else {
+        "updating: "
+    };
+
+    let mut pending = files.clone();
+    let mut steps = Vec::new();
+    if let Some(archive) = &state.archive {
+        for index in 0..archive.len() {
+            let file = archive.by_index_raw(index)?;
+            let name = file.name();
+            let step = match pending.remove(&name) {
+                Some(path) if !update || is_changed(&file, &source_path(&path), args)? => {
+                    Step::Add {
+                        name,
+                        path,
+                        action: replace,
+                    }
+                }
+                None if args.basic_mode_options.filesync => Step::Delete(name),
+                _ => Step::Copy(index),
+            };
+            steps.push(step);
+        }
+    }
+    if !(update && freshen) {
+        steps.extend(pending.into_iter().map(|(name, path)| Step::Add {
+            name,
+            path,
+            action: "  adding: ",
+        }));
+    }
+    Ok(steps)
+}
+
+// 文件比条目新，或者大小不同时需要重新压缩
+// 条目有UT额外字段时按UTC秒比较，否则按2秒精度的DOS本地时间比较
+fn is_changed(file: &ZipFile, path: &Path, args: &cli::ZipArgs) -> Result<bool> {
+    if path.as_os_str() == STDIO_NAME {
+        return Ok(true);
+    }
+    let metadata = if args.other.store_symlinks {
+        fs::symlink_metadata(path)?
+    } else {
+        fs::metadata(path)?
+    };
+    let modified = metadata.modified()?;
+    let newer = match file.ut_modification_time() {
+        Some(time) => chrono::DateTime::<chrono::Utc>::from(modified).timestamp() > time,
+        None => {
+            let (time, date) = datetime_to_dos(&modified.into());
+            let header = file.header();
+            (date, time) > (header.mod_date, header.mod_time)
+        }
+    };
+    // 换行转换会改变数据大小，此时只比较时间
+    let translated = args.translation.convert_lf_to_crlf || args.translation.convert_crlf_to_lf;
+    let resized = !metadata.is_dir() && !translated && metadata.len() != file.origin_size();
+    Ok(newer || resized)
+}
+
+// 按步骤写入条目，返回所有条目中最新的修改时间(-o)
+fn write_entries<W: ZipSink>(
+    state: &mut RunState,
+    writer: &mut ZipWriter<W>,
+    steps: &[Step],
+) -> Result<Option<SystemTime>> {
+    let archive = state.archive.take();
+    if let Some(archive) = &archive {
+        writer.set_comment(&archive.archive_info().comment);
+    }
+    let result = (|| -> Result<()> {
+        for step in steps {
+            match step {
+                Step::Copy(index) => {
+                    if let Some(archive) = &archive {
+                        writer.raw_copy_file(&archive.by_index_raw(*index)?)?;
+                    }
+                }
+                Step::Add { name, path, action } => add_entry(state, writer, action, name, path)?,
+                Step::Delete(name) => {
+                    report(state, &format!("deleting: {}\n", name));
+                    state.changed_files.push(name.clone());
+                }
+            }
+        }
+        Ok(())
+    })();
+    state.archive = archive;
+    result?;
+
+    if state.args.basic_options.add_comments {
+        add_entry_comments(writer, &state.changed_files)?;
+    }
+    if state.args.basic_options.add_archive_comment {
+        writer.set_comment(&read_archive_comment(state)?);
+    }
+    Ok(writer
+        .entries()
+        .iter()
+        .map(|header| (header.mod_date, header.mod_time))
+        .max()
+        .and_then(|(date, time)| dos_to_system_time(date, time)))
+}
+
+fn dos_to_system_time(date: u16, time: u16) -> Option<SystemTime> {
+    use chrono::TimeZone;
+    chrono::Local
+        .with_ymd_and_hms(
+            (date >> 9) as i32 + 1980,
+            ((date >> 5) & 0xF) as u32,
+            (date & 0x1F) as u32,
+            (time >> 11) as u32,
+            ((time >> 5) & 0x3F) as u32,
+            (time & 0x1F) as u32 * 2,
+        )
+        .single()
+        .map(SystemTime::from)
+}
+
+fn add_entry<W: ZipSink>(
+    state: &mut RunState,
+    writer: &mut ZipWriter<W>,
+    action: &str,
+    name: &str,
+    path: &Path,
+) -> Result<()> {
+    let path = source_path(path);
+    report(state, &format!("{}{}", action, name));
+
+    let mut options: FileOptions = state.file_options.clone();
+    let header = if path.as_os_str() == STDIO_NAME {
+        // 标准输入的大小未知，修改时间取当前时间
+        options.large_file = true;
+        options.modification_time = Some(datetime_to_dos(&chrono::Local::now()));
+        writer.start_file(name, options)?;
+        io::copy(&mut io::stdin().lock(), writer)?;
+        writer.finish_file()?
+    } else {
+        options.set_file_path(&path)?;
+        writer.add_file_from_path(name, &path, options)?
+    };
+
+    let original_size = header.get_uncompressed_size();
+    let compressed_size = header.get_compressed_size();
+    state.total_entries += 1;
+    state.total_original_size += original_size;
+    state.total_compressed_size += compressed_size;
+    state.changed_files.push(name.to_string());
+    state.print_operation_end_args(original_size, compressed_size, header.compression);
+    Ok(())
+}
+
+// -c：为本次添加或更新的条目各读取一行注释
+fn add_entry_comments<W: ZipSink>(writer: &mut ZipWriter<W>, changed: &[String]) -> Result<()> {
+    let names: Vec<String> = writer
+        .entries()
+        .iter()
+        .map(|header| String::from_utf8_lossy(&header.filename).to_string())
+        .collect();
+    let mut stdin = io::stdin().lock();
+    for (index, name) in names.iter().enumerate() {
+        if !changed.contains(name) {
+            continue;
+        }
+        eprintln!("Enter comment for {}:", name);
+        let mut line = String::new();
+        if stdin.read_line(&mut line)? == 0 {
+            break;
+        }
+        let comment = line.trim_end_matches(['\r', '\n']);
+        if !comment.is_empty() {
+            writer.set_entry_comment(index, comment);
+        }
+    }
+    Ok(())
+}
+
+// -z：从标准输入读取归档注释，终端输入时以单独一行 "." 结束
+fn read_archive_comment(state: &RunState) -> Result<String> {
+    if !state.quiet {
+        eprintln!("enter new zip file comment (end with .):");
+    }
+    let mut comment = String::new();
+    let mut stdin = io::stdin().lock();
+    let mut line = String::new();
+    while stdin.read_line(&mut line)? > 0 {
+        if line.trim_end_matches(['\r', '\n']) == "." {
+            break;
+        }
+        comment.push_str(&line);
+        line.clear();
+    }
+    if comment.ends_with('\n') {
+        comment.pop();
+    }
+    Ok(comment)
+}
+
+// -m：归档写入成功后删除源文件，目录最后按从深到浅的顺序删除
+fn remove_sources(state: &mut RunState, steps: &[Step]) -> Result<()> {
+    if !state.args.basic_options.move_files {
+        return Ok(());
+    }
+    for step in steps {
+        let Step::Add { path, .. } = step else {
+            continue;
+        };
+        let path = source_path(path);
+        if path.as_os_str() == STDIO_NAME {
+            continue;
+        }
+        if fs::symlink_metadata(&path).is_ok_and(|metadata| metadata.is_dir()) {
+            state.dirs_to_remove.insert(path);
+        } else {
+            fs::remove_file(&path)?;
+        }
+    }
+    let mut dirs: Vec<PathBuf> = state.dirs_to_remove.drain().collect();
+    dirs.sort_by_key(|dir| std::cmp::Reverse(dir.components().count()));
+    for dir in dirs {
+        // 目录中还有未加入归档的文件时保留
+        let _ = fs::remove_dir(&dir);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::zip::ZipArchive;
+
+    #[test]
+    fn test_update_plan() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let (old, new) = (dir.path().join("old.txt"), dir.path().join("new.txt"));
+        fs::write(&old, "old")?;
+        fs::write(&new, "new")?;
+        let zip_path = dir.path().join("a.zip");
+        let mut writer = ZipWriter::new(&zip_path.to_string_lossy())?;
+        let mut options = FileOptions::new();
+        options.set_file_path(&old)?;
+        writer.add_file_from_path("old.txt", &old, options)?;
+        writer.finish()?;
+
+        let mut state = RunState::new(Some(zip_path.clone()));
+        state.archive = Some(ZipArchive::new(&zip_path.to_string_lossy())?);
+        state.args.command = cli::Command::Update;
+        let files = BTreeMap::from([
+            ("old.txt".to_string(), old.clone()),
+            ("new.txt".to_string(), new.clone()),
+        ]);
+        // -u：未变化的条目原样复制，新文件添加到末尾
+        let steps = plan(&state, &files)?;
+        assert!(matches!(
+            steps[..],
+            [
+                Step::Copy(0),
+                Step::Add {
+                    action: "  adding: ",
+                    ..
+                }
+            ]
+        ));
+
+        // -f：只替换大小或时间变化的条目，不添加新文件
+        state.args.basic_mode_options.freshen = true;
+        fs::write(&old, "changed")?;
+        let steps = plan(&state, &files)?;
+        assert!(matches!(
+            steps[..],
+            [Step::Add {
+                action: "freshening: ",
+                ..
+            }]
+        ));
+
+        // --FS：文件系统中不存在的条目被删除
+        state.args.basic_mode_options.freshen = false;
+        state.args.basic_mode_options.filesync = true;
+        let steps = plan(&state, &BTreeMap::new())?;
+        assert!(matches!(&steps[..], [Step::Delete(name)] if name == "old.txt"));
+
+        // 大小不变时只看修改时间：UT额外字段精确到秒，只有DOS时间的条目精确到2秒
+        let (ut, dos) = (dir.path().join("ut.txt"), dir.path().join("dos.txt"));
+        let base = filetime::FileTime::from_unix_time(1_700_000_000, 0);
+        for path in [&ut, &dos] {
+            fs::write(path, "same")?;
+            filetime::set_file_mtime(path, base)?;
+        }
+        let mut writer = ZipWriter::new(&zip_path.to_string_lossy())?;
+        for (name, path, no_extra) in [("ut.txt", &ut, false), ("dos.txt", &dos, true)] {
+            let mut options = FileOptions::new();
+            options.set_file_path(path)?;
+            options.no_extra_field = no_extra;
+            writer.add_file_from_path(name, path, options)?;
+        }
+        writer.finish()?;
+        let archive = ZipArchive::new(&zip_path.to_string_lossy())?;
+        assert!(archive.by_index_raw(0)?.ut_modification_time().is_some());
+        assert!(archive.by_index_raw(1)?.ut_modification_time().is_none());
+        state.archive = Some(archive);
+        state.args.basic_mode_options.filesync = false;
+        state.args.basic_mode_options.freshen = true;
+        let files = BTreeMap::from([
+            ("ut.txt".to_string(), ut.clone()),
+            ("dos.txt".to_string(), dos.clone()),
+        ]);
+        assert!(matches!(
+            plan(&state, &files)?[..],
+            [Step::Copy(0), Step::Copy(1)]
+        ));
+
+        let newer = filetime::FileTime::from_unix_time(1_700_000_001, 0);
+        filetime::set_file_mtime(&ut, newer)?;
+        filetime::set_file_mtime(&dos, newer)?;
+        assert!(matches!(
+            plan(&state, &files)?[..],
+            [Step::Add { .. }, Step::Copy(1)]
+        ));
+        filetime::set_file_mtime(&dos, filetime::FileTime::from_unix_time(1_700_000_004, 0))?;
+        assert!(matches!(
+            plan(&state, &files)?[..],
+            [Step::Add { .. }, Step::Add { .. }]
+        ));
+        Ok(())
+    }
+}
@@ -0,0 +1,109 @@
+/*
+ * SPDX-FileCopyrightText: 2025 UnionTech Software Technology Co., Ltd.
+ *
+ * SPDX-License-Identifier: GPL-2.0-or-later
+ */
+
+// -A：自解压程序之后的归档，条目偏移改为从文件开头计算；-J：去掉归档之前的自解压程序
+use super::add::{write_archive_with_prefix, Step};
+use super::{is_stdout, open_archive};
+use crate::error::ZipError;
+use crate::utils::common::RunState;
+use crate::utils::log::LogConfig;
+use anyhow::Result;
+use std::fs::File;
+use std::io::Read;
+
+pub fn run(state: &mut RunState) -> Result<()> {
+    if is_stdout(state.zip_file.as_deref()) || state.args.split.config().is_some() {
+        return Err(ZipError::InvalidArguments(
+            "cannot adjust a split or streamed archive".to_string(),
+        )
+        .into());
+    }
+    open_archive(state)?;
+    let Some(archive) = state.archive.as_ref() else {
+        return Ok(());
+    };
+    if archive.split_files().is_some() {
+        return Err(ZipError::InvalidArguments(
+            "cannot adjust a split or streamed archive".to_string(),
+        )
+        .into());
+    }
+
+    // 第一个条目(没有条目时为中央目录)之前的数据就是自解压程序
+    let info = archive.archive_info();
+    let cd_offset = info.zip64_offset.unwrap_or(info.offset as u64) + info.offset_shift;
+    let start = archive
+        .entries()
+        .iter()
+        .map(|header| header.get_local_header_offset())
+        .min()
+        .unwrap_or(cd_offset);
+    let shift = info.offset_shift;
+    let steps: Vec<Step> = (0..archive.len()).map(Step::Copy).collect();
+
+    let mut prefix = Vec::new();
+    if !state.args.extractor.junk_sfx {
+        if shift > 0 {
+            LogConfig::println(&format!(
+                "Zip entry offsets appear off by {} bytes - correcting...",
+                shift
+            ));
+        }
+        if let Some(path) = &state.zip_file {
+            File::open(path)?.take(start).read_to_end(&mut prefix)?;
+        }
+    }
+    write_archive_with_prefix(state, &steps, &prefix)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::zip::{FileOptions, ZipArchive, ZipWriter};
+    use std::io::{Cursor, Write};
+
+    #[test]
+    fn test_adjust_and_junk_prefix() -> Result<()> {
+        let mut writer = ZipWriter::from_writer(Cursor::new(Vec::new()));
+        for name in ["a.txt", "b.txt"] {
+            writer.start_file(name, FileOptions::new())?;
+            writer.write_all(name.repeat(100).as_bytes())?;
+        }
+        let archive = writer.finish()?.into_inner();
+
+        // 直接拼接在自解压程序之后，偏移仍然从归档开头计算
+        let stub = b"#!/bin/sh\nexit 0\n".repeat(10);
+        let dir = tempfile::tempdir()?;
+        let zip_path = dir.path().join("sfx.zip");
+        std::fs::write(&zip_path, [&stub[..], &archive[..]].concat())?;
+        let sfx = ZipArchive::new(&zip_path.to_string_lossy())?;
+        assert_eq!(sfx.archive_info().offset_shift, stub.len() as u64);
+        assert!(sfx.check_central_directory().is_err());
+
+        let mut state = RunState::new(Some(zip_path.clone()));
+        state.quiet = true;
+        state.args.command = crate::cli::Command::Adjust;
+        state.args.extractor.adjust_sfx = true;
+        run(&mut state)?;
+        let adjusted = std::fs::read(&zip_path)?;
+        assert_eq!(adjusted[..stub.len()], stub[..]);
+        let sfx = ZipArchive::new(&zip_path.to_string_lossy())?;
+        assert_eq!(sfx.archive_info().offset_shift, 0);
+        sfx.check_central_directory()?;
+        assert_eq!(
+            sfx.entries()[0].get_local_header_offset(),
+            stub.len() as u64
+        );
+
+        state.args.extractor.adjust_sfx = false;
+        state.args.extractor.junk_sfx = true;
+        run(&mut state)?;
+        let junked = ZipArchive::new(&zip_path.to_string_lossy())?;
+        assert_eq!(junked.entries()[0].get_local_header_offset(), 0);
+        junked.by_index_raw(1)?.check_local_header()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,119 @@
+/*
+ * SPDX-FileCopyrightText: 2025 UnionTech Software Technology Co., Ltd.
+ *
+ * SPDX-License-Identifier: GPL-2.0-or-later
+ */
+
+// -U：把匹配的条目原样复制到 --out 指定的归档，加密条目不需要密码
+use super::add::{write_archive, Step};
+use super::{match_entries, open_archive, report, zip_display};
+use crate::error::ZipError;
+use crate::utils::common::{apply_filters, RunState};
+use anyhow::Result;
+
+pub fn run(state: &mut RunState) -> Result<()> {
+    // 与原生zip一致，不指定 --out 时拒绝执行，避免用选中的条目覆盖原归档
+    if state.args.other.out.is_none() {
+        return Err(ZipError::InvalidArguments("-U (--copy) requires --out".to_string()).into());
+    }
+    open_archive(state)?;
+    // 没有文件参数时复制全部条目，再按 -i/-x 筛选
+    let matched = if state.args.files.is_empty() {
+        vec![true; state.archive.as_ref().map_or(0, |archive| archive.len())]
+    } else {
+        match_entries(state)?
+    };
+    let Some(archive) = state.archive.take() else {
+        return Ok(());
+    };
+
+    let mut steps = Vec::new();
+    for (index, matched) in matched.into_iter().enumerate() {
+        let name = archive.by_index_raw(index)?.name();
+        if matched && apply_filters(&name, &state.args, true) {
+            report(state, &format!(" copying: {}\n", name));
+            state.changed_files.push(name);
+            steps.push(Step::Copy(index));
+        }
+    }
+    state.archive = Some(archive);
+    if steps.is_empty() {
+        return Err(ZipError::NothingToDo(zip_display(state)).into());
+    }
+    write_archive(state, &steps)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::zip::{zip64_test_archive, FileOptions, ZipArchive, ZipWriter};
+    use std::io::{Read, Write};
+    use std::path::{Path, PathBuf};
+
+    fn copy_state(zip_path: &Path, pattern: &str) -> RunState {
+        let mut state = RunState::new(Some(zip_path.to_path_buf()));
+        state.quiet = true;
+        state.args.command = crate::cli::Command::Copy;
+        state.args.files = vec![PathBuf::from(pattern)];
+        state
+    }
+
+    #[test]
+    fn test_copy_selected_entries() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let zip_path = dir.path().join("a.zip");
+        let out = dir.path().join("out.zip");
+        let mut writer = ZipWriter::new(&zip_path.to_string_lossy())?;
+        for name in ["a.txt", "b.txt", "c.txt"] {
+            writer.start_file(name, FileOptions::new())?;
+            writer.write_all(name.repeat(100).as_bytes())?;
+        }
+        writer.finish()?;
+        let original = std::fs::read(&zip_path)?;
+
+        // 没有 --out 时报错，原归档不变
+        let error = run(&mut copy_state(&zip_path, "b.*")).unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<ZipError>(),
+            Some(ZipError::InvalidArguments(_))
+        ));
+        assert_eq!(std::fs::read(&zip_path)?, original);
+
+        let mut state = copy_state(&zip_path, "b.*");
+        state.args.other.out = Some(out.clone());
+        run(&mut state)?;
+        assert_eq!(std::fs::read(&zip_path)?, original);
+        let archive = ZipArchive::new(&out.to_string_lossy())?;
+        assert_eq!(archive.len(), 1);
+        let file = archive.by_index_raw(0)?;
+        assert_eq!(file.name(), "b.txt");
+        assert_eq!(file.header().get_local_header_offset(), 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_copy_corrects_zip64_offsets() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let zip_path = dir.path().join("a.zip");
+        let out = dir.path().join("out.zip");
+        std::fs::write(
+            &zip_path,
+            zip64_test_archive(&[("a.txt", b"first"), ("b.txt", b"second")]),
+        )?;
+
+        let mut state = copy_state(&zip_path, "b.txt");
+        state.args.other.out = Some(out.clone());
+        run(&mut state)?;
+
+        // 0x0001额外字段中的偏移按新位置重新写入，本地文件头与中央目录一致
+        let archive = ZipArchive::new(&out.to_string_lossy())?;
+        archive.check_central_directory()?;
+        let file = archive.by_index_raw(0)?;
+        assert_eq!(file.header().get_local_header_offset(), 0);
+        file.check_local_header()?;
+        let mut data = String::new();
+        file.reader(None)?.read_to_string(&mut data)?;
+        assert_eq!(data, "second");
+        Ok(())
+    }
+}
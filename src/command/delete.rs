@@ -0,0 +1,103 @@
+/*
+ * SPDX-FileCopyrightText: 2025 UnionTech Software Technology Co., Ltd.
+ *
+ * SPDX-License-Identifier: GPL-2.0-or-later
+ */
+
+// -d：删除与文件参数匹配的条目，其余条目原样复制，不解压也不重新压缩
+use super::add::{write_archive, Step};
+use super::{match_entries, open_archive, zip_display};
+use crate::error::ZipError;
+use crate::utils::common::{apply_filters, RunState};
+use anyhow::Result;
+
+pub fn run(state: &mut RunState) -> Result<()> {
+    open_archive(state)?;
+    let matched = match_entries(state)?;
+    let Some(archive) = state.archive.as_ref() else {
+        return Ok(());
+    };
+
+    let mut steps = Vec::with_capacity(archive.len());
+    for (index, matched) in matched.into_iter().enumerate() {
+        let name = archive.by_index_raw(index)?.name();
+        // -x 指定的条目即使匹配也保留
+        if matched && !apply_filters(&name, &state.args, true) {
+            steps.push(Step::Delete(name));
+        } else {
+            steps.push(Step::Copy(index));
+        }
+    }
+    if !steps.iter().any(|step| matches!(step, Step::Delete(_))) {
+        return Err(ZipError::NothingToDo(zip_display(state)).into());
+    }
+    write_archive(state, &steps)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::zip::{zip64_test_archive, FileOptions, ZipArchive, ZipWriter};
+    use std::io::{Read, Write};
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_delete_keeps_encrypted_entries() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let zip_path = dir.path().join("a.zip");
+        let mut writer = ZipWriter::new(&zip_path.to_string_lossy())?;
+        for name in ["a.txt", "b.txt", "c.txt"] {
+            let mut options = FileOptions::new();
+            options.with_password("secret");
+            writer.start_file(name, options)?;
+            writer.write_all(name.repeat(100).as_bytes())?;
+        }
+        writer.finish()?;
+
+        let mut state = RunState::new(Some(zip_path.clone()));
+        state.quiet = true;
+        state.args.command = crate::cli::Command::Delete;
+        state.args.files = vec![PathBuf::from("a.*")];
+        run(&mut state)?;
+
+        // 剩余条目的偏移量重新计算，不需要密码就能复制，用原密码仍能解密
+        let archive = ZipArchive::new(&zip_path.to_string_lossy())?;
+        assert_eq!(archive.len(), 2);
+        for (index, name) in ["b.txt", "c.txt"].iter().enumerate() {
+            let file = archive.by_index_raw(index)?;
+            assert_eq!(file.name(), *name);
+            let mut data = String::new();
+            file.reader(Some(b"secret"))?.read_to_string(&mut data)?;
+            assert_eq!(data, name.repeat(100));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_delete_corrects_zip64_offsets() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let zip_path = dir.path().join("a.zip");
+        std::fs::write(
+            &zip_path,
+            zip64_test_archive(&[("a.txt", b"first"), ("b.txt", b"second")]),
+        )?;
+
+        let mut state = RunState::new(Some(zip_path.clone()));
+        state.quiet = true;
+        state.args.command = crate::cli::Command::Delete;
+        state.args.files = vec![PathBuf::from("a.txt")];
+        run(&mut state)?;
+
+        // 剩余条目前移到归档开头，不再沿用0x0001额外字段中的旧偏移
+        let archive = ZipArchive::new(&zip_path.to_string_lossy())?;
+        archive.check_central_directory()?;
+        assert_eq!(archive.len(), 1);
+        let file = archive.by_index_raw(0)?;
+        assert_eq!(file.header().get_local_header_offset(), 0);
+        file.check_local_header()?;
+        let mut data = String::new();
+        file.reader(None)?.read_to_string(&mut data)?;
+        assert_eq!(data, "second");
+        Ok(())
+    }
+}
@@ -0,0 +1,148 @@
+/*
+ * SPDX-FileCopyrightText: 2025 UnionTech Software Technology Co., Ltd.
+ *
+ * SPDX-License-Identifier: GPL-2.0-or-later
+ */
+
+// -F：按中央目录把完好的条目复制到 --out 指定的归档
+// --FF：中央目录损坏或缺失时，扫描本地文件头尽量恢复条目
+use super::add::{write_archive, Step};
+use super::{report, zip_display};
+use crate::error::ZipError;
+use crate::utils::common::RunState;
+use crate::utils::log::LogConfig;
+use crate::zip::ZipArchive;
+use anyhow::Result;
+
+pub fn run(state: &mut RunState) -> Result<()> {
+    let path = state
+        .zip_file
+        .clone()
+        .ok_or_else(|| ZipError::InvalidArguments("missing zipfile".to_string()))?;
+    if !path.exists() {
+        return Err(ZipError::ArchiveNotFound(path).into());
+    }
+    // 修复结果总是写入新的归档，原归档保持不变
+    if state.args.other.out.is_none() {
+        return Err(ZipError::InvalidArguments("-F/-FF require --out".to_string()).into());
+    }
+
+    let archive = if state.args.fix.fix_full {
+        LogConfig::println("Fix archive (-FF) - salvage what can");
+        ZipArchive::scan(&path.to_string_lossy())?
+    } else {
+        LogConfig::println("Fix archive (-F) - assume mostly intact archive");
+        match ZipArchive::new(&path.to_string_lossy()) {
+            Ok(archive) => archive,
+            Err(e) => {
+                LogConfig::println_warning("central directory not usable, try -FF");
+                return Err(e);
+            }
+        }
+    };
+
+    // 本地文件头与中央目录不一致或者数据不完整的条目不再写入
+    let file_len = archive.get_total_size();
+    let mut steps = Vec::new();
+    for index in 0..archive.len() {
+        let name = String::from_utf8_lossy(&archive.entries()[index].filename).to_string();
+        let checked = archive.by_index_raw(index).and_then(|file| {
+            file.check_local_header()?;
+            if file.data_range().1 > file_len {
+                return Err(ZipError::InvalidArchive("entry data truncated".to_string()).into());
+            }
+            Ok(())
+        });
+        match checked {
+            Ok(()) => {
+                report(state, &format!(" copying: {}\n", name));
+                state.changed_files.push(name);
+                steps.push(Step::Copy(index));
+            }
+            Err(e) => LogConfig::println_warning(&format!("skipping {}: {:#}", name, e)),
+        }
+    }
+    state.archive = Some(archive);
+    if steps.is_empty() {
+        return Err(ZipError::NothingToDo(zip_display(state)).into());
+    }
+    write_archive(state, &steps)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::zip::{FileOptions, ZipWriter};
+    use std::io::{Read, Write};
+    use std::path::Path;
+
+    fn fix_state(zip_path: &Path, out: &Path, full: bool) -> RunState {
+        let mut state = RunState::new(Some(zip_path.to_path_buf()));
+        state.quiet = true;
+        state.args.command = crate::cli::Command::Fix;
+        state.args.fix.fix_normal = !full;
+        state.args.fix.fix_full = full;
+        state.args.other.out = Some(out.to_path_buf());
+        state
+    }
+
+    fn entries(path: &Path) -> Result<Vec<(String, String)>> {
+        let archive = ZipArchive::new(&path.to_string_lossy())?;
+        archive.check_central_directory()?;
+        (0..archive.len())
+            .map(|index| {
+                let file = archive.by_index_raw(index)?;
+                let mut data = String::new();
+                file.reader(None)?.read_to_string(&mut data)?;
+                Ok((file.name(), data))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_fix_and_salvage() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let zip_path = dir.path().join("a.zip");
+        let out = dir.path().join("fixed.zip");
+        let mut writer = ZipWriter::new(&zip_path.to_string_lossy())?;
+        for name in ["a.txt", "b.txt", "c.txt"] {
+            writer.start_file(name, FileOptions::new())?;
+            writer.write_all(name.repeat(100).as_bytes())?;
+        }
+        // 流式写入的条目使用数据描述符
+        let mut stream = ZipWriter::new_stream(Vec::new());
+        stream.start_file("d.txt", FileOptions::new())?;
+        stream.write_all(b"streamed")?;
+        let streamed = stream.finish()?.into_inner();
+        writer.finish()?;
+
+        // -F：破坏第二个条目的本地文件头，其余条目照常复制
+        let mut data = std::fs::read(&zip_path)?;
+        let second = ZipArchive::new(&zip_path.to_string_lossy())?.entries()[1]
+            .get_local_header_offset() as usize;
+        data[second + 26] ^= 0xFF;
+        std::fs::write(&zip_path, &data)?;
+        run(&mut fix_state(&zip_path, &out, false))?;
+        let names: Vec<String> = entries(&out)?.into_iter().map(|(name, _)| name).collect();
+        assert_eq!(names, ["a.txt", "c.txt"]);
+
+        // --FF：去掉中央目录后接上使用数据描述符的条目，从本地文件头恢复
+        data[second + 26] ^= 0xFF;
+        let cd_offset = ZipArchive::new(&zip_path.to_string_lossy())?
+            .archive_info()
+            .offset as usize;
+        data.truncate(cd_offset);
+        let entries_end = ZipArchive::from_reader(std::io::Cursor::new(streamed.clone()))?
+            .archive_info()
+            .offset as usize;
+        data.extend_from_slice(&streamed[..entries_end]);
+        std::fs::write(&zip_path, &data)?;
+        assert!(run(&mut fix_state(&zip_path, &out, false)).is_err());
+        run(&mut fix_state(&zip_path, &out, true))?;
+        let recovered = entries(&out)?;
+        assert_eq!(recovered.len(), 4);
+        assert_eq!(recovered[1], ("b.txt".to_string(), "b.txt".repeat(100)));
+        assert_eq!(recovered[3], ("d.txt".to_string(), "streamed".to_string()));
+        Ok(())
+    }
+}
@@ -0,0 +1,39 @@
+/*
+ * SPDX-FileCopyrightText: 2025 UnionTech Software Technology Co., Ltd.
+ *
+ * SPDX-License-Identifier: GPL-2.0-or-later
+ */
+
+// --sf：列出归档中的条目
+use super::open_archive;
+use crate::utils::common::RunState;
+use crate::utils::log::LogConfig;
+use crate::utils::sanitize::{printable_entry_name, sanitize_entry_name};
+use anyhow::Result;
+
+pub fn run(state: &mut RunState) -> Result<()> {
+    open_archive(state)?;
+    let Some(archive) = state.archive.as_ref() else {
+        return Ok(());
+    };
+
+    let mut total_size = 0;
+    LogConfig::println("Archive contains:");
+    for index in 0..archive.len() {
+        let file = archive.by_index_raw(index)?;
+        total_size += file.origin_size();
+        // 与 utunzip 的列表一致，不安全的名称只给出警告，不原样输出到终端
+        match sanitize_entry_name(&file.name()) {
+            Ok(name) => LogConfig::println(&format!("  {}", printable_entry_name(&name))),
+            Err(e) => log::warn!("{}", printable_entry_name(&e.to_string())),
+        }
+    }
+    LogConfig::println(&format!(
+        "Total {} entries ({} bytes)",
+        archive.len(),
+        total_size
+    ));
+    state.total_entries = archive.len();
+    state.total_original_size = total_size;
+    Ok(())
+}
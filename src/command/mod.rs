@@ -0,0 +1,259 @@
+/*
+ * SPDX-FileCopyrightText: 2025 UnionTech Software Technology Co., Ltd.
+ *
+ * SPDX-License-Identifier: GPL-2.0-or-later
+ */
+
+// utzip 主程序：按 cli::Command 分派到各个命令的实现
+mod add;
+mod adjust;
+mod copy;
+mod delete;
+mod fix;
+mod list;
+mod test;
+
+use crate::cli::{self, Command};
+use crate::error::ZipError;
+use crate::utils::common::{match_pattern, read_password, RunState};
+use crate::utils::log::LogConfig;
+use crate::utils::logfile::LogFile;
+use crate::zip::FileOptions;
+use anyhow::Result;
+use std::path::{Component, Path, PathBuf};
+
+// 文件参数中的 "-" 表示压缩标准输入，归档名为 "-" 时写入标准输出
+pub const STDIO_NAME: &str = "-";
+
+// 执行命令行选择的命令，结束时写入并关闭 -lf 日志文件
+pub fn run(mut args: cli::ZipArgs) -> Result<()> {
+    // 与原生zip一致，没有扩展名的归档名自动加上 .zip
+    if let Some(zipfile) = args.zipfile.as_mut() {
+        if zipfile.as_os_str() != STDIO_NAME && zipfile.extension().is_none() && !zipfile.exists() {
+            zipfile.set_extension("zip");
+        }
+    }
+
+    let mut state = RunState::new(args.zipfile.clone());
+    state.set_display_info(&args);
+    state.verbose = args.basic_options.verbose;
+    // 归档写入标准输出时不能再输出进度信息
+    state.quiet = args.basic_options.quiet || is_stdout(args.zipfile.as_deref());
+    if let Some(path) = &args.logging.logfile {
+        let mut log_file = LogFile::new(
+            path.clone(),
+            args.logging.logfile_append,
+            args.logging.logfile_info,
+        );
+        log_file.log_command(&std::env::args().collect::<Vec<_>>())?;
+        state.log_file = Some(log_file);
+    }
+    if matches!(args.command, Command::Add | Command::Update) {
+        state.file_options = file_options(&args)?;
+    }
+    state.args = args;
+
+    let result = match state.args.command {
+        Command::Add | Command::Update => add::run(&mut state),
+        Command::List => list::run(&mut state),
+        Command::Test => test::run(&mut state),
+        Command::Delete => delete::run(&mut state),
+        Command::Copy => copy::run(&mut state),
+        Command::Fix => fix::run(&mut state),
+        Command::Adjust => adjust::run(&mut state),
+    };
+
+    if let Some(log_file) = state.log_file.as_mut() {
+        if let Err(e) = &result {
+            log_file.write_log(&format!("{:#}", e), Some(()))?;
+        }
+        log_file.log_summary(state.total_entries, state.total_original_size)?;
+        log_file.close()?;
+    }
+    result
+}
+
+// 由压缩、加密和换行转换选项生成新条目的 FileOptions
+pub fn file_options(args: &cli::ZipArgs) -> Result<FileOptions> {
+    let mut options = FileOptions::new();
+    options.with_compression(args.compression.method());
+    if let Some(level) = args.compression.level() {
+        options.with_compression_level(level);
+    }
+    options.convert_lf_to_crlf = args.translation.convert_lf_to_crlf;
+    options.convert_crlf_to_lf = args.translation.convert_crlf_to_lf;
+    options.no_extra_field = args.other.no_extra;
+    options.store_symlinks = args.other.store_symlinks;
+
+    // -n 的后缀列表以 ':' 或 ';' 分隔，补充默认不压缩的后缀
+    if let Some(suffixes) = &args.other.dont_compress_suffixes {
+        for suffix in suffixes.split([':', ';']).filter(|s| !s.is_empty()) {
+            let suffix = if suffix.starts_with('.') {
+                suffix.to_string()
+            } else {
+                format!(".{}", suffix)
+            };
+            options.no_compress_extensions.insert(suffix);
+        }
+    }
+
+    let encryption = &args.encryption;
+    let password = match &encryption.password {
+        Some(password) => Some(password.clone()),
+        None if encryption.encrypt || encryption.aes256 => Some(prompt_password()?),
+        None => None,
+    };
+    if let Some(password) = password {
+        if password.is_empty() {
+            return Err(
+                ZipError::InvalidArguments("zero length password not allowed".to_string()).into(),
+            );
+        }
+        options.with_password(&password);
+        options.with_encryption(encryption.method());
+    }
+    Ok(options)
+}
+
+// -e：输入两次密码并确认一致
+fn prompt_password() -> Result<String> {
+    let password = read_password("Enter password: ")?;
+    if read_password("Verify password: ")? != password {
+        return Err(ZipError::InvalidArguments("password verification failed".to_string()).into());
+    }
+    Ok(password)
+}
+
+pub fn is_stdout(zipfile: Option<&Path>) -> bool {
+    zipfile.is_some_and(|path| path.as_os_str() == STDIO_NAME)
+}
+
+// 文件系统路径对应的条目名称：去掉根目录和 '.'，'..' 与前一级目录抵消，
+// 开头多余的 '..' 直接去掉，目录以 '/' 结尾
+pub fn entry_name(path: &Path, junk_paths: bool, is_dir: bool) -> String {
+    let mut name = if junk_paths {
+        path.file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default()
+    } else {
+        let mut parts: Vec<String> = Vec::new();
+        for component in path.components() {
+            match component {
+                Component::Normal(part) => parts.push(part.to_string_lossy().to_string()),
+                Component::ParentDir => {
+                    parts.pop();
+                }
+                _ => {}
+            }
+        }
+        parts.join("/")
+    };
+    if is_dir && !name.is_empty() {
+        name.push('/');
+    }
+    name
+}
+
+// search_pattern_in_archive 返回的归档条目标记，对应文件系统中的同名文件
+pub fn source_path(path: &Path) -> PathBuf {
+    match path.to_string_lossy().strip_prefix("__ZIP_ENTRY__:") {
+        Some(name) => PathBuf::from(name),
+        None => path.to_path_buf(),
+    }
+}
+
+// 归档名称，用于提示信息
+fn zip_display(state: &RunState) -> String {
+    state
+        .zip_file
+        .as_ref()
+        .map_or_else(String::new, |path| path.display().to_string())
+}
+
+// 打开已存在的归档，不存在时返回 ZipError::ArchiveNotFound
+fn open_archive(state: &mut RunState) -> Result<()> {
+    let path = state
+        .zip_file
+        .clone()
+        .ok_or_else(|| ZipError::InvalidArguments("missing zipfile".to_string()))?;
+    if !path.exists() {
+        return Err(ZipError::ArchiveNotFound(path).into());
+    }
+    state.archive = Some(crate::zip::ZipArchive::new(&path.to_string_lossy())?);
+    Ok(())
+}
+
+// 归档中每个条目是否与文件参数匹配(-d/-U)，没有匹配任何条目的参数给出警告
+fn match_entries(state: &RunState) -> Result<Vec<bool>> {
+    let Some(archive) = state.archive.as_ref() else {
+        return Ok(Vec::new());
+    };
+    let args = &state.args;
+    let patterns: Vec<String> = args
+        .files
+        .iter()
+        .map(|path| {
+            let is_dir = path.to_string_lossy().ends_with('/');
+            entry_name(path, false, is_dir)
+        })
+        .collect();
+
+    let mut matched = vec![false; archive.len()];
+    let mut used = vec![false; patterns.len()];
+    for (index, matched) in matched.iter_mut().enumerate() {
+        let name = archive.by_index_raw(index)?.name();
+        for (pattern, used) in patterns.iter().zip(used.iter_mut()) {
+            // --ws 时通配符不跨越目录
+            let same_depth = name.matches('/').count() == pattern.matches('/').count();
+            if (!args.other.no_wildcards_boundary || same_depth)
+                && match_pattern(&name, pattern, args.other.no_wildcards)
+            {
+                *matched = true;
+                *used = true;
+            }
+        }
+    }
+    for (pattern, used) in patterns.iter().zip(used) {
+        if !used {
+            LogConfig::println_warning(&format!("name not matched: {}", pattern));
+        }
+    }
+    Ok(matched)
+}
+
+// 写入日志文件和屏幕的提示行，例如 "  adding: name"
+fn report(state: &mut RunState, message: &str) {
+    if let Some(log_file) = state.log_file.as_mut() {
+        let _ = log_file.write_log(message, None);
+    }
+    if !state.quiet {
+        LogConfig::print(message);
+        use std::io::Write;
+        let _ = std::io::stdout().flush();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_entry_name() {
+        assert_eq!(
+            entry_name(Path::new("./a/../b/c.txt"), false, false),
+            "b/c.txt"
+        );
+        assert_eq!(entry_name(Path::new("q/../q/f"), false, false), "q/f");
+        assert_eq!(entry_name(Path::new("x/../f"), false, false), "f");
+        assert_eq!(entry_name(Path::new("../x/f"), false, false), "x/f");
+        assert_eq!(
+            entry_name(Path::new("/etc/conf.d"), false, true),
+            "etc/conf.d/"
+        );
+        assert_eq!(entry_name(Path::new("dir/c.txt"), true, false), "c.txt");
+        assert_eq!(
+            source_path(Path::new("__ZIP_ENTRY__:a/b")),
+            Path::new("a/b")
+        );
+    }
+}
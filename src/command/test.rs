@@ -0,0 +1,179 @@
+/*
+ * SPDX-FileCopyrightText: 2025 UnionTech Software Technology Co., Ltd.
+ *
+ * SPDX-License-Identifier: GPL-2.0-or-later
+ */
+
+// -T：检查归档的完整性，--TT 指定外部命令时改用该命令检查
+use super::zip_display;
+use crate::error::ZipError;
+use crate::utils::common::{read_password, RunState};
+use crate::utils::log::LogConfig;
+use crate::zip::ZipArchive;
+use anyhow::Result;
+use std::io;
+use std::path::Path;
+use std::process::Command;
+
+pub fn run(state: &mut RunState) -> Result<()> {
+    let path = state
+        .zip_file
+        .clone()
+        .ok_or_else(|| ZipError::InvalidArguments("missing zipfile".to_string()))?;
+    if !path.exists() {
+        return Err(ZipError::ArchiveNotFound(path).into());
+    }
+    test_archive(state, &path)
+}
+
+// 检查 path 处的归档，提示信息使用目标归档的名称
+pub fn test_archive(state: &RunState, path: &Path) -> Result<()> {
+    let display = zip_display(state);
+    let passed = match &state.args.test.test_cmd {
+        Some(command) => run_command(command, path),
+        None => match verify(state, path) {
+            Ok(failed) => failed == 0,
+            Err(e) => {
+                log::error!("{}: {:#}", display, e);
+                false
+            }
+        },
+    };
+    if passed {
+        LogConfig::println(&format!("test of {} OK", display));
+        Ok(())
+    } else {
+        LogConfig::println(&format!("test of {} FAILED", display));
+        Err(ZipError::TestFailed(display).into())
+    }
+}
+
+// 内部检查：结束记录与中央目录一致，本地文件头与中央目录记录一致，
+// 每个条目解压后CRC和大小正确。返回失败的条目数
+fn verify(state: &RunState, path: &Path) -> Result<usize> {
+    let archive = ZipArchive::new(&path.to_string_lossy())?;
+    archive.check_central_directory()?;
+
+    let mut password = state
+        .file_options
+        .password
+        .clone()
+        .or_else(|| state.args.encryption.password.clone());
+    let mut failed = 0;
+    for index in 0..archive.len() {
+        let file = archive.by_index_raw(index)?;
+        let name = file.name();
+        if file.encrypted() && password.is_none() {
+            password = Some(read_password(&format!(
+                "[{}] {} password: ",
+                path.display(),
+                name
+            ))?);
+        }
+        let result = file
+            .check_local_header()
+            .and_then(|_| file.reader(password.as_deref().map(str::as_bytes)))
+            .map_err(anyhow::Error::from)
+            .and_then(|mut reader| Ok(io::copy(&mut reader, &mut io::sink())?));
+        match result {
+            Ok(_) => LogConfig::println(&format!("    testing: {:<40} OK", name)),
+            Err(e) => {
+                LogConfig::println(&format!("    testing: {:<40} FAIL", name));
+                log::error!("{}: {:#}", name, e);
+                failed += 1;
+            }
+        }
+    }
+    Ok(failed)
+}
+
+// --TT：命令中的 {} 替换为归档路径，否则追加在命令末尾
+fn run_command(command: &str, path: &Path) -> bool {
+    let archive = shell_quote(&path.to_string_lossy());
+    let command = if command.contains("{}") {
+        command.replace("{}", &archive)
+    } else {
+        format!("{} {}", command, archive)
+    };
+    Command::new("sh")
+        .arg("-c")
+        .arg(&command)
+        .status()
+        .is_ok_and(|status| status.success())
+}
+
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::zip::{FileOptions, ZipWriter};
+    use std::io::Write;
+
+    #[test]
+    fn test_verify_detects_corruption() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("a.zip");
+        let mut writer = ZipWriter::new(&path.to_string_lossy())?;
+        for name in ["a.txt", "b.txt"] {
+            writer.start_file(name, FileOptions::new())?;
+            writer.write_all(name.repeat(1000).as_bytes())?;
+        }
+        writer.finish()?;
+        let state = RunState::new(Some(path.clone()));
+        assert_eq!(verify(&state, &path)?, 0);
+
+        // 改动第一个条目的压缩数据，以及第二个本地文件头中的压缩方法
+        let archive = ZipArchive::new(&path.to_string_lossy())?;
+        let (data_start, _) = archive.by_index_raw(0)?.data_range();
+        let second = archive.by_index_raw(1)?.header().get_local_header_offset();
+        let mut data = std::fs::read(&path)?;
+        data[data_start as usize + 2] ^= 0xFF;
+        data[second as usize + 8] = 0;
+        std::fs::write(&path, data)?;
+        assert_eq!(verify(&state, &path)?, 2);
+        Ok(())
+    }
+
+    #[test]
+    fn test_verify_detects_end_record_mismatch() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("a.zip");
+        let state = RunState::new(Some(path.clone()));
+        let check = |data: &[u8], message: &str| -> Result<()> {
+            std::fs::write(&path, data)?;
+            let error = verify(&state, &path).unwrap_err();
+            assert!(error.to_string().contains(message), "{}", error);
+            Ok(())
+        };
+
+        // 16位条目数与ZIP64结束目录中的条目数不一致
+        let zip64 = crate::zip::zip64_test_archive(&[("a.txt", b"a"), ("b.txt", b"b")]);
+        std::fs::write(&path, &zip64)?;
+        assert_eq!(verify(&state, &path)?, 0);
+        let mut data = zip64.clone();
+        let end = data.len() - 22;
+        data[end + 10..end + 12].copy_from_slice(&1u16.to_le_bytes());
+        check(
+            &data,
+            "end record lists 1 entries, ZIP64 end record lists 2",
+        )?;
+
+        // 中央目录与结束记录之间多出数据
+        let mut writer = ZipWriter::new(&path.to_string_lossy())?;
+        writer.start_file("a.txt", FileOptions::new())?;
+        writer.write_all(b"a")?;
+        writer.finish()?;
+        let plain = std::fs::read(&path)?;
+        let end = plain.len() - 22;
+        let data = [&plain[..end], &[0u8; 4], &plain[end..]].concat();
+        check(&data, "central directory ends at")?;
+
+        // 归档前面有多余数据且没有调整偏移
+        let data = [&[0u8; 4][..], &plain[..]].concat();
+        check(&data, "4 extra bytes")?;
+        Ok(())
+    }
+}
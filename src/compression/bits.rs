@@ -0,0 +1,125 @@
+/*
+ * SPDX-FileCopyrightText: 2025 UnionTech Software Technology Co., Ltd.
+ *
+ * SPDX-License-Identifier: GPL-2.0-or-later
+ */
+
+// 旧式压缩方法(Shrink/Reduce/Implode)共用的位读取器和滑动窗口
+use std::io::{self, BufReader, Read};
+
+pub(crate) fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+// 按低位优先的顺序读取位流
+pub(crate) struct BitReader<R: Read> {
+    inner: BufReader<R>,
+    bits: u64,
+    count: u32,
+}
+
+impl<R: Read> BitReader<R> {
+    pub fn new(inner: R) -> Self {
+        Self {
+            inner: BufReader::new(inner),
+            bits: 0,
+            count: 0,
+        }
+    }
+
+    // 读取n(不超过32)位，数据不足时返回UnexpectedEof
+    pub fn read_bits(&mut self, n: u32) -> io::Result<u32> {
+        debug_assert!(n <= 32);
+        while self.count < n {
+            let mut byte = [0u8; 1];
+            self.inner.read_exact(&mut byte)?;
+            self.bits |= (byte[0] as u64) << self.count;
+            self.count += 8;
+        }
+        let value = (self.bits & ((1u64 << n) - 1)) as u32;
+        self.bits >>= n;
+        self.count -= n;
+        Ok(value)
+    }
+
+    pub fn read_bit(&mut self) -> io::Result<bool> {
+        Ok(self.read_bits(1)? == 1)
+    }
+
+    pub fn read_u8(&mut self) -> io::Result<u8> {
+        Ok(self.read_bits(8)? as u8)
+    }
+}
+
+// 已输出数据的滑动窗口，窗口开始之前的数据按0处理
+pub(crate) struct Window {
+    buf: Vec<u8>,
+    pos: usize,
+}
+
+impl Window {
+    // size必须是2的幂
+    pub fn new(size: usize) -> Self {
+        debug_assert!(size.is_power_of_two());
+        Self {
+            buf: vec![0; size],
+            pos: 0,
+        }
+    }
+
+    pub fn push(&mut self, byte: u8) {
+        let mask = self.buf.len() - 1;
+        self.buf[self.pos & mask] = byte;
+        self.pos = self.pos.wrapping_add(1);
+    }
+
+    // 取距离当前位置dist(从1开始)处的字节
+    pub fn back(&self, dist: usize) -> u8 {
+        let mask = self.buf.len() - 1;
+        self.buf[self.pos.wrapping_sub(dist) & mask]
+    }
+}
+
+// 带有剩余长度的回溯复制
+#[derive(Debug, Default, Clone, Copy)]
+pub(crate) struct BackRef {
+    pub dist: usize,
+    pub remaining: usize,
+}
+
+// 测试中用于构造位流
+#[cfg(test)]
+#[derive(Default)]
+pub(crate) struct BitWriter {
+    bytes: Vec<u8>,
+    bits: u64,
+    count: u32,
+}
+
+#[cfg(test)]
+impl BitWriter {
+    pub fn put(&mut self, value: u32, n: u32) {
+        self.bits |= ((value as u64) & ((1u64 << n) - 1)) << self.count;
+        self.count += n;
+        while self.count >= 8 {
+            self.bytes.push(self.bits as u8);
+            self.bits >>= 8;
+            self.count -= 8;
+        }
+    }
+
+    pub fn finish(mut self) -> Vec<u8> {
+        if self.count > 0 {
+            self.bytes.push(self.bits as u8);
+        }
+        self.bytes
+    }
+}
+
+// 方法1-6的测试数据共用的原文。对应的压缩数据由不经本项目代码、按APPNOTE独立实现的编码器生成，
+// 并用 Info-ZIP UnZip 6.00(unshrink、explode) 和 hwzip 的解码器(三种方法)校验过
+#[cfg(test)]
+pub(crate) const DICKENS: &[u8] = b"It was the best of times, it was the worst of times, \
+it was the age of wisdom, it was the age of foolishness, it was the epoch of belief, \
+it was the epoch of incredulity, it was the season of Light, it was the season of Darkness, \
+it was the spring of hope, it was the winter of despair.\n";
@@ -0,0 +1,231 @@
+/*
+ * SPDX-FileCopyrightText: 2025 UnionTech Software Technology Co., Ltd.
+ *
+ * SPDX-License-Identifier: GPL-2.0-or-later
+ */
+
+// Implode(方法6)解码：滑动窗口 + Shannon-Fano编码
+// 通用标志位1表示8K窗口(否则4K)，位2表示存在字面量编码树(否则字面量按8位原样存放)
+use super::bits::{invalid_data, BackRef, BitReader, Window};
+use std::io::{self, Read};
+
+const LARGE_WINDOW_FLAG: u16 = 0x2;
+const LITERAL_TREE_FLAG: u16 = 0x4;
+const MAX_CODE_LENGTH: usize = 16;
+const WINDOW_SIZE: usize = 8192;
+// 长度编码为63时再读取一个字节加到长度上
+const EXTENDED_LENGTH: u16 = 63;
+
+// Shannon-Fano编码树
+// 按位取反后等价于规范哈夫曼编码：码长短的在前，码长相同时符号值小的在前
+struct Tree {
+    counts: [u16; MAX_CODE_LENGTH + 1],
+    symbols: Vec<u16>,
+}
+
+impl Tree {
+    // 编码树的存储格式：首字节为后续字节数减一，
+    // 之后每个字节低4位为码长减一，高4位为连续使用该码长的符号数减一
+    fn read<R: Read>(input: &mut BitReader<R>, symbol_count: usize) -> io::Result<Self> {
+        let bytes = input.read_u8()? as usize + 1;
+        let mut lengths = Vec::with_capacity(symbol_count);
+        for _ in 0..bytes {
+            let byte = input.read_u8()?;
+            let length = (byte & 0x0f) + 1;
+            let run = (byte >> 4) as usize + 1;
+            if lengths.len() + run > symbol_count {
+                return Err(invalid_data("implode: too many code lengths"));
+            }
+            lengths.extend(std::iter::repeat(length).take(run));
+        }
+        if lengths.len() != symbol_count {
+            return Err(invalid_data("implode: missing code lengths"));
+        }
+
+        let mut counts = [0u16; MAX_CODE_LENGTH + 1];
+        for &length in &lengths {
+            counts[length as usize] += 1;
+        }
+        let mut left: i32 = 1;
+        for &count in &counts[1..] {
+            left = (left << 1) - count as i32;
+            if left < 0 {
+                return Err(invalid_data("implode: over-subscribed code tree"));
+            }
+        }
+
+        let mut symbols = Vec::with_capacity(symbol_count);
+        for length in 1..=MAX_CODE_LENGTH as u8 {
+            for (symbol, _) in lengths.iter().enumerate().filter(|(_, &l)| l == length) {
+                symbols.push(symbol as u16);
+            }
+        }
+        Ok(Self { counts, symbols })
+    }
+
+    fn decode<R: Read>(&self, input: &mut BitReader<R>) -> io::Result<u16> {
+        let (mut code, mut first, mut index) = (0i32, 0i32, 0i32);
+        for &count in &self.counts[1..] {
+            code |= !input.read_bit()? as i32;
+            let count = count as i32;
+            if code - first < count {
+                return Ok(self.symbols[(index + code - first) as usize]);
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+        Err(invalid_data("implode: invalid code"))
+    }
+}
+
+pub struct ImplodeDecoder<R: Read> {
+    input: BitReader<R>,
+    literal_tree: Option<Tree>,
+    length_tree: Tree,
+    distance_tree: Tree,
+    distance_low_bits: u32,
+    min_length: usize,
+    window: Window,
+    pending: BackRef,
+    remaining: u64,
+}
+
+impl<R: Read> ImplodeDecoder<R> {
+    // flags为条目的通用标志位
+    pub fn new(inner: R, flags: u16, uncompressed_size: u64) -> io::Result<Self> {
+        let mut input = BitReader::new(inner);
+        let literal_tree = if flags & LITERAL_TREE_FLAG != 0 {
+            Some(Tree::read(&mut input, 256)?)
+        } else {
+            None
+        };
+        let length_tree = Tree::read(&mut input, 64)?;
+        let distance_tree = Tree::read(&mut input, 64)?;
+        Ok(Self {
+            input,
+            min_length: if literal_tree.is_some() { 3 } else { 2 },
+            literal_tree,
+            length_tree,
+            distance_tree,
+            distance_low_bits: if flags & LARGE_WINDOW_FLAG != 0 { 7 } else { 6 },
+            window: Window::new(WINDOW_SIZE),
+            pending: BackRef::default(),
+            remaining: uncompressed_size,
+        })
+    }
+
+    // 解码一个字面量或一次回溯，字面量直接返回
+    fn decode_next(&mut self) -> io::Result<Option<u8>> {
+        if self.input.read_bit()? {
+            let literal = match &self.literal_tree {
+                Some(tree) => tree.decode(&mut self.input)? as u8,
+                None => self.input.read_u8()?,
+            };
+            return Ok(Some(literal));
+        }
+
+        let low = self.input.read_bits(self.distance_low_bits)? as usize;
+        let high = self.distance_tree.decode(&mut self.input)? as usize;
+        let symbol = self.length_tree.decode(&mut self.input)?;
+        let mut length = symbol as usize + self.min_length;
+        if symbol == EXTENDED_LENGTH {
+            length += self.input.read_u8()? as usize;
+        }
+        self.pending = BackRef {
+            dist: (high << self.distance_low_bits | low) + 1,
+            remaining: length,
+        };
+        Ok(None)
+    }
+}
+
+impl<R: Read> Read for ImplodeDecoder<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let mut written = 0;
+        while written < buf.len() && self.remaining > 0 {
+            let byte = if self.pending.remaining > 0 {
+                self.pending.remaining -= 1;
+                self.window.back(self.pending.dist)
+            } else {
+                match self.decode_next()? {
+                    Some(byte) => byte,
+                    None => continue,
+                }
+            };
+            self.window.push(byte);
+            buf[written] = byte;
+            written += 1;
+            self.remaining -= 1;
+        }
+        Ok(written)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::bits::{BitWriter, DICKENS};
+    use super::*;
+
+    // 按码长6写入符号：高位在前且按位取反
+    fn put_code(writer: &mut BitWriter, symbol: u32) {
+        for shift in (0..6).rev() {
+            writer.put(!(symbol >> shift) & 1, 1);
+        }
+    }
+
+    #[test]
+    fn test_explode() {
+        let mut writer = BitWriter::default();
+        // 长度树和距离树：64个符号的码长都为6
+        for _ in 0..2 {
+            for byte in [3, 0xf5, 0xf5, 0xf5, 0xf5] {
+                writer.put(byte, 8);
+            }
+        }
+        for byte in b"xy" {
+            writer.put(1, 1);
+            writer.put(*byte as u32, 8);
+        }
+        // 距离2(低6位为1，高位符号0)，长度6(无字面量树时最小长度为2)
+        writer.put(0, 1);
+        writer.put(1, 6);
+        put_code(&mut writer, 0);
+        put_code(&mut writer, 6 - 2);
+        let imploded = writer.finish();
+
+        let mut output = Vec::new();
+        ImplodeDecoder::new(&imploded[..], 0, 8)
+            .unwrap()
+            .read_to_end(&mut output)
+            .unwrap();
+        assert_eq!(output, b"xyxyxyxy");
+    }
+
+    // 8K字典、三棵树(通用标志位 0x06)，码长由字符频率计算
+    const DICKENS_IMPLODED: [u8; 194] = [
+        0x30, 0x98, 0x07, 0xf8, 0x48, 0x05, 0xa8, 0x07, 0x08, 0x07, 0xf8, 0x48, 0x07, 0x38, 0x07,
+        0x18, 0x07, 0xf8, 0x38, 0x05, 0x26, 0x04, 0x16, 0x05, 0x04, 0x08, 0x07, 0x16, 0x05, 0x04,
+        0x05, 0x08, 0x05, 0x04, 0x05, 0x07, 0x08, 0x06, 0x08, 0x07, 0xf8, 0x38, 0xf7, 0xf7, 0xf7,
+        0xf7, 0xf7, 0xf7, 0xf7, 0x17, 0x0a, 0x06, 0x03, 0x46, 0x05, 0x16, 0x05, 0x04, 0xf5, 0xf5,
+        0xf5, 0x35, 0x05, 0x02, 0x05, 0xd6, 0xf5, 0xf5, 0xf5, 0xeb, 0x8f, 0xf7, 0xad, 0xdb, 0x73,
+        0x3f, 0x5e, 0xff, 0xfb, 0xe1, 0xff, 0x1c, 0xef, 0xef, 0xde, 0xfd, 0xf8, 0x6d, 0xff, 0xcf,
+        0xa6, 0xfb, 0x27, 0xf3, 0x73, 0xeb, 0x3d, 0xd1, 0xbc, 0xbb, 0xed, 0xfe, 0x30, 0xff, 0xad,
+        0xef, 0xd9, 0x7f, 0xb7, 0x65, 0x3e, 0xed, 0xbd, 0xef, 0xce, 0xf7, 0x5c, 0x2f, 0xff, 0x03,
+        0xf7, 0xfb, 0x9f, 0xdf, 0x83, 0x2b, 0xd0, 0xff, 0xf0, 0xdf, 0xf9, 0xfe, 0x3d, 0x9b, 0x0f,
+        0xdf, 0xe5, 0xe0, 0xf4, 0xef, 0xaf, 0xd9, 0xf9, 0x8e, 0xab, 0x81, 0x7e, 0x3c, 0xff, 0xed,
+        0x79, 0x2f, 0x42, 0xff, 0x75, 0xdf, 0xee, 0xf5, 0x68, 0xf3, 0x7e, 0xc3, 0xed, 0xb4, 0xd6,
+        0xfd, 0xeb, 0x73, 0x3e, 0x7d, 0x97, 0x5d, 0x9e, 0xff, 0xf5, 0x3d, 0xff, 0xcc, 0xfb, 0xfd,
+        0x2e, 0xc7, 0xff, 0x44, 0xf3, 0xdf, 0xff, 0x9f, 0xf3, 0xed, 0x3b, 0x6d, 0xdc, 0x0c,
+    ];
+
+    #[test]
+    fn test_explode_fixture() {
+        let mut output = Vec::new();
+        ImplodeDecoder::new(&DICKENS_IMPLODED[..], 0x06, DICKENS.len() as u64)
+            .unwrap()
+            .read_to_end(&mut output)
+            .unwrap();
+        assert_eq!(output, DICKENS);
+    }
+}
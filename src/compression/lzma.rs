@@ -0,0 +1,155 @@
+/*
+ * SPDX-FileCopyrightText: 2025 UnionTech Software Technology Co., Ltd.
+ *
+ * SPDX-License-Identifier: GPL-2.0-or-later
+ */
+
+// ZIP中的LZMA(方法14)数据格式：
+//   2字节LZMA SDK版本 + 2字节属性长度(5) + 5字节属性 + 原始LZMA数据流
+// liblzma只支持.lzma(LZMA_alone)格式：5字节属性 + 8字节原始大小 + 数据流，
+// 因此写入时替换文件头，读取时重新构造文件头
+use std::io::{self, Cursor, Read, Write};
+use xz2::stream::{LzmaOptions, Stream};
+
+// 写入的LZMA SDK版本号
+const LZMA_VERSION: [u8; 2] = [9, 20];
+const LZMA_PROPS_SIZE: usize = 5;
+// LZMA_alone文件头：属性 + 8字节原始大小
+const LZMA_ALONE_HEADER_SIZE: usize = LZMA_PROPS_SIZE + 8;
+// 解码时的内存上限。字典大小来自不可信的归档，不加限制时一个很小的条目就能让解码器
+// 分配数GiB内存；-9 预设的字典为64MiB，256MiB足以解开常见工具生成的数据
+const DECODER_MEMLIMIT: u64 = 256 * 1024 * 1024;
+
+fn to_io_error<E: std::error::Error + Send + Sync + 'static>(e: E) -> io::Error {
+    io::Error::new(io::ErrorKind::Other, e)
+}
+
+// 将LZMA_alone编码器的输出转换为ZIP格式：丢弃原文件头，写入ZIP的LZMA头
+pub struct LzmaHeaderWriter<W: Write> {
+    inner: W,
+    header: Vec<u8>,
+}
+
+impl<W: Write> LzmaHeaderWriter<W> {
+    pub fn new(inner: W) -> Self {
+        Self {
+            inner,
+            header: Vec::with_capacity(LZMA_ALONE_HEADER_SIZE),
+        }
+    }
+
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+impl<W: Write> Write for LzmaHeaderWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.header.len() < LZMA_ALONE_HEADER_SIZE {
+            let take = buf.len().min(LZMA_ALONE_HEADER_SIZE - self.header.len());
+            self.header.extend_from_slice(&buf[..take]);
+            if self.header.len() == LZMA_ALONE_HEADER_SIZE {
+                let mut zip_header = Vec::with_capacity(4 + LZMA_PROPS_SIZE);
+                zip_header.extend_from_slice(&LZMA_VERSION);
+                zip_header.extend_from_slice(&(LZMA_PROPS_SIZE as u16).to_le_bytes());
+                zip_header.extend_from_slice(&self.header[..LZMA_PROPS_SIZE]);
+                self.inner.write_all(&zip_header)?;
+            }
+            return Ok(take);
+        }
+        self.inner.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+// LZMA编码器，数据流总是以结束标记(EOS)结尾
+pub fn lzma_encoder<W: Write>(
+    writer: W,
+    level: u32,
+) -> io::Result<xz2::write::XzEncoder<LzmaHeaderWriter<W>>> {
+    let options = LzmaOptions::new_preset(level.min(9)).map_err(to_io_error)?;
+    let stream = Stream::new_lzma_encoder(&options).map_err(to_io_error)?;
+    Ok(xz2::write::XzEncoder::new_stream(
+        LzmaHeaderWriter::new(writer),
+        stream,
+    ))
+}
+
+pub fn finish_lzma_encoder<W: Write>(
+    encoder: xz2::write::XzEncoder<LzmaHeaderWriter<W>>,
+) -> io::Result<W> {
+    Ok(encoder.finish()?.into_inner())
+}
+
+// LZMA解码器，has_eos对应通用标志位1；没有结束标记时按原始大小结束
+pub fn lzma_decoder<R: Read>(
+    mut reader: R,
+    has_eos: bool,
+    uncompressed_size: u64,
+) -> io::Result<xz2::read::XzDecoder<io::Chain<Cursor<Vec<u8>>, R>>> {
+    let mut zip_header = [0u8; 4];
+    reader.read_exact(&mut zip_header)?;
+    let props_size = u16::from_le_bytes([zip_header[2], zip_header[3]]) as usize;
+    if props_size != LZMA_PROPS_SIZE {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("unsupported LZMA properties size {}", props_size),
+        ));
+    }
+
+    let mut alone_header = vec![0u8; LZMA_PROPS_SIZE];
+    reader.read_exact(&mut alone_header)?;
+    let size = if has_eos { u64::MAX } else { uncompressed_size };
+    alone_header.extend_from_slice(&size.to_le_bytes());
+
+    let stream = Stream::new_lzma_decoder(DECODER_MEMLIMIT).map_err(to_io_error)?;
+    Ok(xz2::read::XzDecoder::new_stream(
+        Cursor::new(alone_header).chain(reader),
+        stream,
+    ))
+}
+
+// XZ解码器，使用与LZMA相同的内存上限
+pub fn xz_decoder<R: Read>(reader: R) -> io::Result<xz2::read::XzDecoder<R>> {
+    let stream = Stream::new_stream_decoder(DECODER_MEMLIMIT, 0).map_err(to_io_error)?;
+    Ok(xz2::read::XzDecoder::new_stream(reader, stream))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_memlimit(mut decoder: impl Read) {
+        let error = decoder.read_to_end(&mut Vec::new()).unwrap_err();
+        assert!(error.to_string().contains("memory limit"), "{}", error);
+    }
+
+    #[test]
+    fn test_decoders_reject_oversized_dictionary() {
+        // LZMA属性中的字典大小改为1GiB
+        let mut encoder = lzma_encoder(Vec::new(), 0).unwrap();
+        encoder.write_all(b"lzma data").unwrap();
+        let mut data = finish_lzma_encoder(encoder).unwrap();
+        data[5..9].copy_from_slice(&(1u32 << 30).to_le_bytes());
+        assert_memlimit(lzma_decoder(Cursor::new(data), true, 0).unwrap());
+
+        // 把XZ块头中LZMA2过滤器的字典大小改为1GiB，并重新计算块头的CRC32
+        let mut encoder = xz2::write::XzEncoder::new(Vec::new(), 0);
+        encoder.write_all(b"xz data").unwrap();
+        let mut data = encoder.finish().unwrap();
+        let block = 12;
+        let header_size = (data[block] as usize + 1) * 4;
+        assert_eq!(data[block + 2], 0x21);
+        data[block + 4] = 36;
+        let crc = crc32fast::hash(&data[block..block + header_size - 4]);
+        data[block + header_size - 4..block + header_size].copy_from_slice(&crc.to_le_bytes());
+        assert_memlimit(xz_decoder(Cursor::new(data)).unwrap());
+    }
+}
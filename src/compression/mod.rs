@@ -0,0 +1,14 @@
+/*
+ * SPDX-FileCopyrightText: 2025 UnionTech Software Technology Co., Ltd.
+ *
+ * SPDX-License-Identifier: GPL-2.0-or-later
+ */
+
+// 需要额外封装的压缩方法
+pub mod lzma;
+
+// 只支持解压的旧式压缩方法
+mod bits;
+pub mod implode;
+pub mod reduce;
+pub mod shrink;
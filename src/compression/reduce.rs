@@ -0,0 +1,204 @@
+/*
+ * SPDX-FileCopyrightText: 2025 UnionTech Software Technology Co., Ltd.
+ *
+ * SPDX-License-Identifier: GPL-2.0-or-later
+ */
+
+// Reduce(方法2-5，压缩因子1-4)解码
+// 先用跟随集(follower set)解码出字节序列，再按DLE(0x90)展开长度/距离回溯
+use super::bits::{invalid_data, BackRef, BitReader, Window};
+use std::io::{self, Read};
+
+const DLE: u8 = 0x90;
+const MAX_FOLLOWERS: usize = 32;
+// 因子4时最大距离为 15*256+255+1
+const WINDOW_SIZE: usize = 4096;
+
+#[derive(Clone, Copy, Default)]
+struct FollowerSet {
+    followers: [u8; MAX_FOLLOWERS],
+    len: u8,
+}
+
+// 跟随集索引所需的位数
+fn index_bits(len: u8) -> u32 {
+    match len {
+        0 => 0,
+        1 => 1,
+        len => 8 - (len - 1).leading_zeros(),
+    }
+}
+
+pub struct ReduceDecoder<R: Read> {
+    input: BitReader<R>,
+    follower_sets: Box<[FollowerSet; 256]>,
+    factor: u32,
+    last_byte: u8,
+    window: Window,
+    pending: BackRef,
+    remaining: u64,
+}
+
+impl<R: Read> ReduceDecoder<R> {
+    // factor为压缩方法减一(1-4)
+    pub fn new(inner: R, factor: u8, uncompressed_size: u64) -> io::Result<Self> {
+        if !(1..=4).contains(&factor) {
+            return Err(invalid_data("reduce: invalid compression factor"));
+        }
+        let mut input = BitReader::new(inner);
+        let mut follower_sets = Box::new([FollowerSet::default(); 256]);
+        for set in follower_sets.iter_mut().rev() {
+            let len = input.read_bits(6)? as u8;
+            if len as usize > MAX_FOLLOWERS {
+                return Err(invalid_data("reduce: follower set too large"));
+            }
+            for follower in set.followers.iter_mut().take(len as usize) {
+                *follower = input.read_u8()?;
+            }
+            set.len = len;
+        }
+        Ok(Self {
+            input,
+            follower_sets,
+            factor: factor as u32,
+            last_byte: 0,
+            window: Window::new(WINDOW_SIZE),
+            pending: BackRef::default(),
+            remaining: uncompressed_size,
+        })
+    }
+
+    // 根据前一个字节的跟随集解码下一个字节
+    fn next_byte(&mut self) -> io::Result<u8> {
+        let set = self.follower_sets[self.last_byte as usize];
+        let byte = if set.len == 0 || self.input.read_bit()? {
+            self.input.read_u8()?
+        } else {
+            let index = self.input.read_bits(index_bits(set.len))? as usize;
+            if index >= set.len as usize {
+                return Err(invalid_data("reduce: invalid follower index"));
+            }
+            set.followers[index]
+        };
+        self.last_byte = byte;
+        Ok(byte)
+    }
+
+    // 解码一个字面量或一次回溯，字面量直接返回
+    fn decode_next(&mut self) -> io::Result<Option<u8>> {
+        let byte = self.next_byte()?;
+        if byte != DLE {
+            return Ok(Some(byte));
+        }
+        let v = self.next_byte()?;
+        if v == 0 {
+            return Ok(Some(DLE));
+        }
+
+        let length_bits = 8 - self.factor;
+        let length_mask = (1u32 << length_bits) - 1;
+        let mut length = v as u32 & length_mask;
+        if length == length_mask {
+            length += self.next_byte()? as u32;
+        }
+        let dist = ((v as u32 >> length_bits) << 8) + self.next_byte()? as u32 + 1;
+        self.pending = BackRef {
+            dist: dist as usize,
+            remaining: length as usize + 3,
+        };
+        Ok(None)
+    }
+}
+
+impl<R: Read> Read for ReduceDecoder<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let mut written = 0;
+        while written < buf.len() && self.remaining > 0 {
+            let byte = if self.pending.remaining > 0 {
+                self.pending.remaining -= 1;
+                self.window.back(self.pending.dist)
+            } else {
+                match self.decode_next()? {
+                    Some(byte) => byte,
+                    None => continue,
+                }
+            };
+            self.window.push(byte);
+            buf[written] = byte;
+            written += 1;
+            self.remaining -= 1;
+        }
+        Ok(written)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::bits::{BitWriter, DICKENS};
+    use super::*;
+
+    #[test]
+    fn test_unreduce() {
+        let mut writer = BitWriter::default();
+        // 256个空跟随集，之后每个字节按8位原样存放
+        for _ in 0..256 {
+            writer.put(0, 6);
+        }
+        for byte in b"abc" {
+            writer.put(*byte as u32, 8);
+        }
+        // 因子1：长度6、距离3的回溯，然后是转义的DLE字面量
+        for byte in [DLE, 6 - 3, 3 - 1, DLE, 0] {
+            writer.put(byte as u32, 8);
+        }
+        let reduced = writer.finish();
+
+        let mut output = Vec::new();
+        ReduceDecoder::new(&reduced[..], 1, 10)
+            .unwrap()
+            .read_to_end(&mut output)
+            .unwrap();
+        assert_eq!(output, b"abcabcabc\x90");
+    }
+
+    // 压缩因子4(方法5)，带跟随集，包含需要额外长度字节的回溯
+    const DICKENS_REDUCED: [u8; 393] = [
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x40, 0x90, 0x06, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x18, 0x01, 0x0f, 0x0b, 0x07, 0x0a,
+        0x0e, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x10, 0x40, 0x02, 0x44, 0x58, 0xda, 0xdb, 0x1c, 0x10, 0xb0, 0x19, 0x20, 0x65,
+        0x68, 0x69, 0x79, 0x90, 0x09, 0x1c, 0x08, 0x0b, 0x59, 0x19, 0xda, 0x1b, 0x1d, 0x64, 0x01,
+        0xe9, 0x52, 0x96, 0xb6, 0x06, 0x10, 0x61, 0x65, 0x6f, 0x72, 0xc8, 0x98, 0x19, 0x5b, 0x9b,
+        0xdb, 0x1b, 0x9c, 0x5c, 0x31, 0x56, 0x76, 0x46, 0x07, 0x29, 0x94, 0x41, 0x06, 0x69, 0x01,
+        0x24, 0x80, 0xb8, 0xcd, 0x95, 0x9d, 0xb5, 0xc9, 0xd1, 0x41, 0x16, 0x65, 0x6e, 0x6f, 0x74,
+        0x90, 0x43, 0x19, 0x1a, 0xe4, 0x00, 0xf2, 0x06, 0x99, 0xcc, 0x41, 0x82, 0x84, 0x91, 0x99,
+        0xb1, 0xc1, 0xc9, 0x0d, 0x65, 0x6f, 0x75, 0x02, 0x9a, 0x5c, 0x50, 0x46, 0xcc, 0x9d, 0xa5,
+        0xc9, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x04, 0x69, 0x00, 0x10, 0xd0, 0x01, 0x00, 0x00, 0x04, 0x61, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x04, 0x65, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x40, 0xa0, 0x00,
+        0x04, 0x20, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x10, 0x30, 0x19, 0x62, 0x74, 0x69,
+        0x6f, 0x73, 0x77, 0x40, 0x80, 0x06, 0x00, 0x02, 0x51, 0x9a, 0x10, 0x46, 0x26, 0x98, 0xdd,
+        0x05, 0x77, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x84, 0x00, 0x41, 0x81, 0x41, 0x70, 0x07,
+        0x00, 0x02, 0x8e, 0x79, 0x00, 0x02, 0x00, 0x41, 0x46, 0xa0, 0x11, 0x6c, 0x04, 0x1b, 0x40,
+        0x90, 0x51, 0x60, 0x68, 0x78, 0x80, 0x84, 0x04, 0x49, 0x00, 0x0a, 0x08, 0x21, 0x20, 0x00,
+        0x70, 0x60, 0x02, 0x31, 0x04, 0x10, 0xc8, 0x0d, 0xe9, 0x20, 0x06, 0x41, 0x00, 0x20, 0x31,
+        0x32, 0x05, 0x20, 0x25, 0x44, 0x89, 0x00, 0x48, 0xe0, 0x08, 0x20, 0x18, 0xc0, 0x20, 0x45,
+        0x11, 0x02, 0x48, 0x88, 0xc0, 0x08, 0x82, 0xc8, 0x80, 0x21, 0x02, 0x82, 0xc9, 0x54, 0x08,
+        0x18, 0x51, 0x0c, 0x6c, 0x40, 0x04, 0x82, 0x58, 0x82, 0x04, 0x4c, 0x20, 0x80, 0x08, 0x00,
+        0x40, 0x15, 0x00,
+    ];
+
+    #[test]
+    fn test_unreduce_fixture() {
+        let mut output = Vec::new();
+        ReduceDecoder::new(&DICKENS_REDUCED[..], 4, DICKENS.len() as u64)
+            .unwrap()
+            .read_to_end(&mut output)
+            .unwrap();
+        assert_eq!(output, DICKENS);
+    }
+}
@@ -0,0 +1,205 @@
+/*
+ * SPDX-FileCopyrightText: 2025 UnionTech Software Technology Co., Ltd.
+ *
+ * SPDX-License-Identifier: GPL-2.0-or-later
+ */
+
+// Shrink(方法1)解码：动态LZW，码长从9位增长到13位
+// 码256为控制码，后跟1表示码长加一，后跟2表示部分清除(释放所有叶子节点)
+use super::bits::{invalid_data, BitReader};
+use std::io::{self, Read};
+
+const MIN_CODE_SIZE: u32 = 9;
+const MAX_CODE_SIZE: u32 = 13;
+const TABLE_SIZE: usize = 1 << MAX_CODE_SIZE;
+const CONTROL_CODE: u16 = 256;
+const INC_CODE_SIZE: u16 = 1;
+const PARTIAL_CLEAR: u16 = 2;
+const FIRST_FREE: u16 = 257;
+// 未使用或已被清除的码
+const FREE: u16 = u16::MAX;
+
+pub struct ShrinkDecoder<R: Read> {
+    input: BitReader<R>,
+    // 每个码对应的前缀码和末尾字节，字面量(0-255)的前缀无意义
+    parent: Vec<u16>,
+    value: Vec<u8>,
+    code_size: u32,
+    next_free: u16,
+    prev_code: Option<u16>,
+    first_byte: u8,
+    // 倒序存放的待输出字节
+    stack: Vec<u8>,
+    remaining: u64,
+}
+
+impl<R: Read> ShrinkDecoder<R> {
+    pub fn new(inner: R, uncompressed_size: u64) -> Self {
+        let mut parent = vec![FREE; TABLE_SIZE];
+        let mut value = vec![0u8; TABLE_SIZE];
+        for code in 0..256 {
+            parent[code] = 0;
+            value[code] = code as u8;
+        }
+        Self {
+            input: BitReader::new(inner),
+            parent,
+            value,
+            code_size: MIN_CODE_SIZE,
+            next_free: FIRST_FREE,
+            prev_code: None,
+            first_byte: 0,
+            stack: Vec::new(),
+            remaining: uncompressed_size,
+        }
+    }
+
+    // 读取下一个数据码，期间处理控制码
+    fn read_code(&mut self) -> io::Result<u16> {
+        loop {
+            let code = self.input.read_bits(self.code_size)? as u16;
+            if code != CONTROL_CODE {
+                return Ok(code);
+            }
+            match self.input.read_bits(self.code_size)? as u16 {
+                INC_CODE_SIZE if self.code_size < MAX_CODE_SIZE => self.code_size += 1,
+                PARTIAL_CLEAR => self.partial_clear(),
+                _ => return Err(invalid_data("shrink: invalid control code")),
+            }
+        }
+    }
+
+    fn partial_clear(&mut self) {
+        let mut has_child = vec![false; TABLE_SIZE];
+        for code in FIRST_FREE as usize..TABLE_SIZE {
+            let parent = self.parent[code];
+            if parent != FREE && parent >= FIRST_FREE {
+                has_child[parent as usize] = true;
+            }
+        }
+        for (code, has_child) in has_child.iter().enumerate().skip(FIRST_FREE as usize) {
+            if !has_child {
+                self.parent[code] = FREE;
+            }
+        }
+        self.next_free = FIRST_FREE;
+        self.advance_free();
+    }
+
+    fn advance_free(&mut self) {
+        while (self.next_free as usize) < TABLE_SIZE && self.parent[self.next_free as usize] != FREE
+        {
+            self.next_free += 1;
+        }
+    }
+
+    // 解码一个码，将其字符串倒序压入栈中
+    fn decode_next(&mut self) -> io::Result<()> {
+        let code = self.read_code()?;
+        let Some(prev_code) = self.prev_code else {
+            if code >= CONTROL_CODE {
+                return Err(invalid_data("shrink: first code must be a literal"));
+            }
+            self.stack.push(code as u8);
+            self.first_byte = code as u8;
+            self.prev_code = Some(code);
+            return Ok(());
+        };
+
+        let mut current = code;
+        if current >= FIRST_FREE && self.parent[current as usize] == FREE {
+            // KwKwK：使用了即将加入的码，其内容为上一个字符串加上它的首字节
+            if current != self.next_free {
+                return Err(invalid_data("shrink: reference to unused code"));
+            }
+            self.stack.push(self.first_byte);
+            current = prev_code;
+        }
+        while current >= FIRST_FREE {
+            if self.stack.len() >= TABLE_SIZE || self.parent[current as usize] == FREE {
+                return Err(invalid_data("shrink: invalid code chain"));
+            }
+            self.stack.push(self.value[current as usize]);
+            current = self.parent[current as usize];
+        }
+        if current == CONTROL_CODE {
+            return Err(invalid_data("shrink: invalid code chain"));
+        }
+        self.first_byte = current as u8;
+        self.stack.push(self.first_byte);
+
+        // 上一个字符串加上当前字符串的首字节构成新码
+        if (self.next_free as usize) < TABLE_SIZE {
+            self.parent[self.next_free as usize] = prev_code;
+            self.value[self.next_free as usize] = self.first_byte;
+            self.advance_free();
+        }
+        self.prev_code = Some(code);
+        Ok(())
+    }
+}
+
+impl<R: Read> Read for ShrinkDecoder<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let mut written = 0;
+        while written < buf.len() && self.remaining > 0 {
+            match self.stack.pop() {
+                Some(byte) => {
+                    buf[written] = byte;
+                    written += 1;
+                    self.remaining -= 1;
+                }
+                None => self.decode_next()?,
+            }
+        }
+        Ok(written)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::bits::DICKENS;
+    use super::*;
+
+    #[test]
+    fn test_unshrink() {
+        // LZW论文图5的示例
+        let shrunk = [
+            0x61, 0xc4, 0x04, 0x1c, 0x23, 0xb0, 0x60, 0x98, 0x83, 0x08, 0xc3, 0x00,
+        ];
+        let expected = b"ababcbababaaaaaaa";
+        let mut output = Vec::new();
+        ShrinkDecoder::new(&shrunk[..], expected.len() as u64)
+            .read_to_end(&mut output)
+            .unwrap();
+        assert_eq!(output, expected);
+    }
+
+    // 第40个码之前把码长增加到10位，第80个码之前部分清除，之后重新使用被清除的码
+    const DICKENS_SHRUNK: [u8; 214] = [
+        0x49, 0xe8, 0x80, 0xb8, 0x13, 0x66, 0x0e, 0x08, 0x3a, 0x68, 0xca, 0x80, 0x10, 0x53, 0x66,
+        0x8e, 0xc0, 0x37, 0x66, 0x0e, 0xa6, 0x69, 0xd3, 0x90, 0x05, 0x88, 0x34, 0x02, 0x09, 0x1a,
+        0x44, 0xa8, 0xf0, 0xce, 0x1b, 0x39, 0x0e, 0x41, 0x40, 0x94, 0x48, 0x71, 0x8e, 0x45, 0x8c,
+        0x00, 0x03, 0x0c, 0x54, 0xd0, 0x41, 0x09, 0x81, 0x10, 0xc6, 0x19, 0x0a, 0x8d, 0x74, 0x47,
+        0x1a, 0x73, 0x90, 0xf1, 0x46, 0x1b, 0x27, 0x65, 0xa4, 0x12, 0x47, 0x2d, 0xbd, 0x24, 0x52,
+        0x44, 0x66, 0xbc, 0xf1, 0x06, 0x1b, 0x33, 0xa1, 0xe1, 0x46, 0x43, 0x26, 0x5d, 0x94, 0xd3,
+        0x46, 0x2c, 0x95, 0x01, 0xc7, 0x1b, 0x63, 0x00, 0x24, 0x00, 0x1a, 0x22, 0x45, 0x24, 0x46,
+        0x19, 0x6c, 0xa4, 0x51, 0x86, 0x19, 0x27, 0xe5, 0xb4, 0x11, 0x4b, 0x65, 0xc0, 0xf1, 0x46,
+        0x40, 0x0c, 0x5d, 0xe4, 0xc6, 0x18, 0x72, 0x94, 0x41, 0x46, 0x1d, 0x1a, 0xd1, 0x91, 0x07,
+        0x48, 0x03, 0xa9, 0xc4, 0x11, 0x08, 0x73, 0x94, 0x51, 0xd0, 0x1b, 0x6e, 0xc0, 0xc4, 0x44,
+        0x1a, 0x67, 0xa0, 0x41, 0x47, 0x4f, 0x04, 0x91, 0xa4, 0x90, 0x50, 0x44, 0x19, 0x35, 0x12,
+        0x11, 0x61, 0xc8, 0xb1, 0x86, 0x1b, 0x0d, 0x99, 0x74, 0x51, 0x48, 0x07, 0xb1, 0x34, 0x07,
+        0x1c, 0x72, 0xa4, 0xe1, 0xc6, 0x19, 0x30, 0xa1, 0xf1, 0x06, 0x1c, 0x65, 0x34, 0xf5, 0x13,
+        0x4b, 0x77, 0x8c, 0x45, 0x47, 0x19, 0x72, 0xc0, 0x44, 0x46, 0x43, 0x70, 0x84, 0x91, 0x86,
+        0x1c, 0x2e, 0x28, 0x00,
+    ];
+
+    #[test]
+    fn test_unshrink_fixture() {
+        let mut output = Vec::new();
+        ShrinkDecoder::new(&DICKENS_SHRUNK[..], DICKENS.len() as u64)
+            .read_to_end(&mut output)
+            .unwrap();
+        assert_eq!(output, DICKENS);
+    }
+}
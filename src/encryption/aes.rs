@@ -0,0 +1,403 @@
+/*
+ * SPDX-FileCopyrightText: 2025 UnionTech Software Technology Co., Ltd.
+ *
+ * SPDX-License-Identifier: GPL-2.0-or-later
+ */
+
+// WinZip AES加密(AE-1/AE-2)
+// 数据格式：盐值(8/12/16字节) + 2字节密码校验值 + AES-CTR密文 + 10字节HMAC-SHA1认证码
+// 密钥由PBKDF2-HMAC-SHA1(1000次迭代)从密码和盐值派生：加密密钥 + 认证密钥 + 校验值
+// 压缩方法字段固定为99，实际压缩方法记录在0x9901额外字段中
+use super::{Decryptor, Encryptor};
+use crate::error::ZipError;
+use crate::zip::{find_extra_field, CompressionMethod};
+
+use aes::cipher::{KeyIvInit, StreamCipher};
+use hmac::{Hmac, Mac};
+use sha1::Sha1;
+use std::io::{self, Write};
+
+pub const AES_EXTRA_FIELD_ID: u16 = 0x9901;
+const AES_VENDOR_ID: &[u8; 2] = b"AE";
+const PBKDF2_ITERATIONS: u32 = 1000;
+const PASSWORD_VERIFIER_SIZE: usize = 2;
+pub const AUTHENTICATION_CODE_SIZE: usize = 10;
+
+type Aes128Ctr = ctr::Ctr128LE<aes::Aes128>;
+type Aes192Ctr = ctr::Ctr128LE<aes::Aes192>;
+type Aes256Ctr = ctr::Ctr128LE<aes::Aes256>;
+type HmacSha1 = Hmac<Sha1>;
+
+// 密钥长度
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum AesStrength {
+    Aes128,
+    Aes192,
+    #[default]
+    Aes256,
+}
+
+impl AesStrength {
+    pub fn from_byte(byte: u8) -> Option<Self> {
+        match byte {
+            1 => Some(Self::Aes128),
+            2 => Some(Self::Aes192),
+            3 => Some(Self::Aes256),
+            _ => None,
+        }
+    }
+
+    pub fn to_byte(self) -> u8 {
+        match self {
+            Self::Aes128 => 1,
+            Self::Aes192 => 2,
+            Self::Aes256 => 3,
+        }
+    }
+
+    pub fn key_size(self) -> usize {
+        match self {
+            Self::Aes128 => 16,
+            Self::Aes192 => 24,
+            Self::Aes256 => 32,
+        }
+    }
+
+    pub fn salt_size(self) -> usize {
+        self.key_size() / 2
+    }
+
+    // 加密后数据比明文多出的字节数
+    pub fn overhead(self) -> u64 {
+        (self.salt_size() + PASSWORD_VERIFIER_SIZE + AUTHENTICATION_CODE_SIZE) as u64
+    }
+}
+
+// AE-1保留CRC32，AE-2不记录CRC32(只依靠认证码校验数据)
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum AesVendorVersion {
+    Ae1,
+    #[default]
+    Ae2,
+}
+
+// 0x9901额外字段
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AesExtraField {
+    pub version: AesVendorVersion,
+    pub strength: AesStrength,
+    pub compression: CompressionMethod,
+}
+
+impl AesExtraField {
+    pub const SIZE: usize = 11;
+
+    pub fn to_bytes(&self) -> [u8; Self::SIZE] {
+        let version: u16 = match self.version {
+            AesVendorVersion::Ae1 => 1,
+            AesVendorVersion::Ae2 => 2,
+        };
+        let mut bytes = [0u8; Self::SIZE];
+        bytes[0..2].copy_from_slice(&AES_EXTRA_FIELD_ID.to_le_bytes());
+        bytes[2..4].copy_from_slice(&7u16.to_le_bytes());
+        bytes[4..6].copy_from_slice(&version.to_le_bytes());
+        bytes[6..8].copy_from_slice(AES_VENDOR_ID);
+        bytes[8] = self.strength.to_byte();
+        bytes[9..11].copy_from_slice(&self.compression.to_le_bytes());
+        bytes
+    }
+
+    // 从条目的额外字段中解析
+    pub fn parse(extra: &[u8]) -> Result<Self, ZipError> {
+        let invalid =
+            |reason: &str| ZipError::InvalidArchive(format!("AES extra field {}", reason));
+        let data = find_extra_field(extra, AES_EXTRA_FIELD_ID).ok_or_else(|| invalid("missing"))?;
+        if data.len() < 7 || &data[2..4] != AES_VENDOR_ID {
+            return Err(invalid("malformed"));
+        }
+        let version = match u16::from_le_bytes([data[0], data[1]]) {
+            1 => AesVendorVersion::Ae1,
+            2 => AesVendorVersion::Ae2,
+            version => {
+                return Err(ZipError::UnsupportedFeature(format!(
+                    "AES vendor version {}",
+                    version
+                )))
+            }
+        };
+        let strength = AesStrength::from_byte(data[4])
+            .ok_or_else(|| ZipError::UnsupportedFeature(format!("AES strength {}", data[4])))?;
+        Ok(Self {
+            version,
+            strength,
+            compression: CompressionMethod::from(u16::from_le_bytes([data[5], data[6]])),
+        })
+    }
+}
+
+enum AesCtr {
+    Aes128(Aes128Ctr),
+    Aes192(Aes192Ctr),
+    Aes256(Aes256Ctr),
+}
+
+impl AesCtr {
+    fn apply_keystream(&mut self, data: &mut [u8]) {
+        match self {
+            Self::Aes128(cipher) => cipher.apply_keystream(data),
+            Self::Aes192(cipher) => cipher.apply_keystream(data),
+            Self::Aes256(cipher) => cipher.apply_keystream(data),
+        }
+    }
+}
+
+// 派生出的密钥材料
+struct AesKeys {
+    cipher: AesCtr,
+    hmac: HmacSha1,
+    verifier: [u8; PASSWORD_VERIFIER_SIZE],
+}
+
+impl AesKeys {
+    fn derive(password: &[u8], salt: &[u8], strength: AesStrength) -> Self {
+        let key_size = strength.key_size();
+        let mut derived = vec![0u8; key_size * 2 + PASSWORD_VERIFIER_SIZE];
+        pbkdf2::pbkdf2_hmac::<Sha1>(password, salt, PBKDF2_ITERATIONS, &mut derived);
+
+        let (encryption_key, rest) = derived.split_at(key_size);
+        let (authentication_key, verifier) = rest.split_at(key_size);
+        // 计数器为小端序，从1开始
+        let mut counter = [0u8; 16];
+        counter[0] = 1;
+        let cipher = match strength {
+            AesStrength::Aes128 => {
+                AesCtr::Aes128(Aes128Ctr::new(encryption_key.into(), &counter.into()))
+            }
+            AesStrength::Aes192 => {
+                AesCtr::Aes192(Aes192Ctr::new(encryption_key.into(), &counter.into()))
+            }
+            AesStrength::Aes256 => {
+                AesCtr::Aes256(Aes256Ctr::new(encryption_key.into(), &counter.into()))
+            }
+        };
+        Self {
+            cipher,
+            hmac: HmacSha1::new_from_slice(authentication_key).expect("HMAC accepts any key size"),
+            verifier: [verifier[0], verifier[1]],
+        }
+    }
+}
+
+// 流式AES加密器：第一次写入时输出盐值和校验值，结束时输出认证码
+pub struct AesEncryptor<W: Write> {
+    inner: W,
+    strength: AesStrength,
+    keys: AesKeys,
+    header: Option<Vec<u8>>,
+    buffer: Vec<u8>,
+}
+
+impl<W: Write> AesEncryptor<W> {
+    pub fn new(inner: W, password: &str, strength: AesStrength) -> io::Result<Self> {
+        use rand::RngCore;
+        let mut salt = vec![0u8; strength.salt_size()];
+        rand::rng().fill_bytes(&mut salt);
+        let keys = AesKeys::derive(password.as_bytes(), &salt, strength);
+
+        let mut header = salt;
+        header.extend_from_slice(&keys.verifier);
+        Ok(Self {
+            inner,
+            strength,
+            keys,
+            header: Some(header),
+            buffer: Vec::new(),
+        })
+    }
+
+    fn write_header(&mut self) -> io::Result<()> {
+        if let Some(header) = self.header.take() {
+            self.inner.write_all(&header)?;
+        }
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> io::Result<W> {
+        // 空文件同样需要盐值、校验值和认证码
+        self.write_header()?;
+        let code = self.keys.hmac.finalize().into_bytes();
+        self.inner.write_all(&code[..AUTHENTICATION_CODE_SIZE])?;
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+}
+
+impl<W: Write> Write for AesEncryptor<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.write_header()?;
+        self.buffer.clear();
+        self.buffer.extend_from_slice(buf);
+        self.keys.cipher.apply_keystream(&mut self.buffer);
+        self.keys.hmac.update(&self.buffer);
+        self.inner.write_all(&self.buffer)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+impl<W: Write> Encryptor<W> for AesEncryptor<W> {
+    fn header_size(&self) -> u64 {
+        (self.strength.salt_size() + PASSWORD_VERIFIER_SIZE) as u64
+    }
+
+    fn trailer_size(&self) -> u64 {
+        AUTHENTICATION_CODE_SIZE as u64
+    }
+
+    fn finish(self: Box<Self>) -> io::Result<W> {
+        AesEncryptor::finish(*self)
+    }
+}
+
+// AES解密器：校验密码校验值，密文解密前先计入认证码
+pub struct AesDecryptor {
+    password: Vec<u8>,
+    strength: AesStrength,
+    keys: Option<AesKeys>,
+}
+
+impl AesDecryptor {
+    pub fn new(password: &[u8], strength: AesStrength) -> Self {
+        Self {
+            password: password.to_vec(),
+            strength,
+            keys: None,
+        }
+    }
+}
+
+impl Decryptor for AesDecryptor {
+    fn header_size(&self) -> u64 {
+        (self.strength.salt_size() + PASSWORD_VERIFIER_SIZE) as u64
+    }
+
+    fn trailer_size(&self) -> u64 {
+        AUTHENTICATION_CODE_SIZE as u64
+    }
+
+    fn check_header(&mut self, header: &[u8]) -> Result<(), ZipError> {
+        let (salt, verifier) = header.split_at(self.strength.salt_size());
+        let keys = AesKeys::derive(&self.password, salt, self.strength);
+        if keys.verifier != verifier {
+            return Err(ZipError::InvalidPassword);
+        }
+        self.keys = Some(keys);
+        Ok(())
+    }
+
+    fn decrypt(&mut self, data: &mut [u8]) {
+        if let Some(keys) = self.keys.as_mut() {
+            keys.hmac.update(data);
+            keys.cipher.apply_keystream(data);
+        }
+    }
+
+    fn check_trailer(&mut self, trailer: &[u8]) -> Result<(), ZipError> {
+        let keys = self.keys.take().ok_or(ZipError::InvalidPassword)?;
+        keys.hmac.verify_truncated_left(trailer).map_err(|_| {
+            ZipError::AuthenticationFailed("AES authentication code mismatch".to_string())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::DecryptionReader;
+    use super::*;
+    use std::io::Read;
+
+    fn open<'a>(
+        data: &'a [u8],
+        password: &[u8],
+        strength: AesStrength,
+    ) -> Result<DecryptionReader<&'a [u8]>, ZipError> {
+        let decryptor = Box::new(AesDecryptor::new(password, strength));
+        DecryptionReader::new(data, decryptor, data.len() as u64)
+    }
+
+    #[test]
+    fn test_aes_round_trip() {
+        let data = b"AES protected deliverable\n".repeat(100);
+        for strength in [
+            AesStrength::Aes128,
+            AesStrength::Aes192,
+            AesStrength::Aes256,
+        ] {
+            let mut encryptor = AesEncryptor::new(Vec::new(), "secret", strength).unwrap();
+            encryptor.write_all(&data[..10]).unwrap();
+            encryptor.write_all(&data[10..]).unwrap();
+            let encrypted = encryptor.finish().unwrap();
+            assert_eq!(
+                encrypted.len() as u64,
+                data.len() as u64 + strength.overhead()
+            );
+
+            let mut output = Vec::new();
+            open(&encrypted, b"secret", strength)
+                .unwrap()
+                .read_to_end(&mut output)
+                .unwrap();
+            assert_eq!(output, data);
+
+            assert!(matches!(
+                open(&encrypted, b"wrong", strength),
+                Err(ZipError::InvalidPassword)
+            ));
+
+            // 篡改密文后认证码校验失败
+            let mut tampered = encrypted.clone();
+            tampered[strength.salt_size() + 5] ^= 1;
+            let mut reader = open(&tampered, b"secret", strength).unwrap();
+            assert!(reader.read_to_end(&mut Vec::new()).is_err());
+        }
+    }
+
+    #[test]
+    fn test_aes_known_answer() {
+        // bsdtar 3.8.2 生成的AE-2存储条目:
+        // bsdtar --format zip --options zip:encryption=aes256,zip:compression=store
+        //        --passphrase utzip-kat -cf kat.zip short.txt
+        let extra = [
+            0x01, 0x99, 0x07, 0x00, 0x02, 0x00, 0x41, 0x45, 0x03, 0x00, 0x00,
+        ];
+        let field = AesExtraField::parse(&extra).unwrap();
+        assert_eq!(
+            field,
+            AesExtraField {
+                version: AesVendorVersion::Ae2,
+                strength: AesStrength::Aes256,
+                compression: CompressionMethod::Stored,
+            }
+        );
+        assert_eq!(field.to_bytes(), extra);
+
+        let encrypted = [
+            0xdc, 0x6a, 0x80, 0x42, 0x45, 0x32, 0xbe, 0xf2, 0x8c, 0x49, 0xda, 0x3c, 0x19, 0x5e,
+            0x4a, 0x99, 0x47, 0xc2, 0xe6, 0x0f, 0xd7, 0x49, 0xe4, 0x48, 0x0e, 0x2a, 0x7c, 0x28,
+            0x15, 0x0c, 0xc4, 0x2f, 0xc5, 0xf0, 0x63, 0xc9, 0xdf, 0xbe, 0x0c, 0xfc,
+        ];
+        let mut output = Vec::new();
+        open(&encrypted, b"utzip-kat", field.strength)
+            .unwrap()
+            .read_to_end(&mut output)
+            .unwrap();
+        assert_eq!(output, b"bsdtar AE-2\n");
+        assert!(matches!(
+            open(&encrypted, b"utzip", field.strength),
+            Err(ZipError::InvalidPassword)
+        ));
+    }
+}
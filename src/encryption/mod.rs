@@ -4,4 +4,236 @@
  * SPDX-License-Identifier: GPL-2.0-or-later
  */
 
+pub mod aes;
 pub mod zipcrypt;
+
+use self::aes::{AesEncryptor, AesStrength, AesVendorVersion};
+use self::zipcrypt::ZipCryptoEncryptor;
+use crate::error::ZipError;
+use std::io::{self, Read, Write};
+
+// 设置密码后条目使用的加密方式
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum EncryptionMethod {
+    // 传统PKWARE加密
+    #[default]
+    ZipCrypto,
+    Aes(AesStrength, AesVendorVersion),
+}
+
+// 加密器：位于压缩编码器和输出之间的写入层
+// 加密数据 = 头部(header_size字节) + 密文(与明文等长) + 尾部(trailer_size字节)
+pub trait Encryptor<W: Write>: Write {
+    // 密文之前的头部大小，ZipCrypto为12字节加密头，AES为盐值和密码校验值
+    fn header_size(&self) -> u64;
+
+    // 密文之后的尾部大小，AES为10字节认证码
+    fn trailer_size(&self) -> u64;
+
+    // 写出剩余的头部/尾部并返回底层写入器
+    fn finish(self: Box<Self>) -> io::Result<W>;
+}
+
+// 解密器：由 DecryptionReader 驱动，只处理数据本身，不负责读取
+pub trait Decryptor: Send {
+    fn header_size(&self) -> u64;
+
+    fn trailer_size(&self) -> u64;
+
+    // 处理密文之前的头部并校验密码
+    fn check_header(&mut self, header: &[u8]) -> Result<(), ZipError>;
+
+    // 原地解密一块密文
+    fn decrypt(&mut self, data: &mut [u8]);
+
+    // 处理密文之后的尾部，默认没有尾部
+    fn check_trailer(&mut self, _trailer: &[u8]) -> Result<(), ZipError> {
+        Ok(())
+    }
+}
+
+// 压缩编码器的输出层：不加密时直接写入底层写入器
+pub enum EncryptionWriter<W: Write> {
+    Plain(W),
+    ZipCrypto(ZipCryptoEncryptor<W>),
+    Aes(Box<AesEncryptor<W>>),
+}
+
+impl<W: Write> EncryptionWriter<W> {
+    // 按加密方式创建加密层
+    // verifier 只用于ZipCrypto，其最高字节写入加密头用于校验密码(CRC32或者 修改时间<<16)
+    pub fn new(
+        writer: W,
+        password: Option<&str>,
+        method: EncryptionMethod,
+        verifier: u32,
+    ) -> io::Result<Self> {
+        Ok(match (password, method) {
+            (None, _) => Self::Plain(writer),
+            (Some(password), EncryptionMethod::ZipCrypto) => {
+                Self::ZipCrypto(ZipCryptoEncryptor::new(writer, password, verifier)?)
+            }
+            (Some(password), EncryptionMethod::Aes(strength, _)) => {
+                Self::Aes(Box::new(AesEncryptor::new(writer, password, strength)?))
+            }
+        })
+    }
+
+    // 加密后数据比明文多出的字节数
+    pub fn overhead(&self) -> u64 {
+        match self {
+            Self::Plain(_) => 0,
+            Self::ZipCrypto(encryptor) => encryptor.header_size() + encryptor.trailer_size(),
+            Self::Aes(encryptor) => encryptor.header_size() + encryptor.trailer_size(),
+        }
+    }
+
+    pub fn finish(self) -> io::Result<W> {
+        match self {
+            Self::Plain(mut writer) => {
+                writer.flush()?;
+                Ok(writer)
+            }
+            Self::ZipCrypto(encryptor) => encryptor.finish(),
+            Self::Aes(encryptor) => (*encryptor).finish(),
+        }
+    }
+}
+
+impl<W: Write> Write for EncryptionWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Self::Plain(writer) => writer.write(buf),
+            Self::ZipCrypto(encryptor) => encryptor.write(buf),
+            Self::Aes(encryptor) => encryptor.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Self::Plain(writer) => writer.flush(),
+            Self::ZipCrypto(encryptor) => encryptor.flush(),
+            Self::Aes(encryptor) => encryptor.flush(),
+        }
+    }
+}
+
+// 解密读取层：读取并校验头部，解密data_size范围内的密文，读完后校验尾部
+pub struct DecryptionReader<R: Read> {
+    inner: R,
+    decryptor: Box<dyn Decryptor>,
+    remaining: u64,
+    trailer_checked: bool,
+}
+
+impl<R: Read> DecryptionReader<R> {
+    // data_size为条目数据的总长度(包含头部和尾部)
+    pub fn new(
+        mut inner: R,
+        mut decryptor: Box<dyn Decryptor>,
+        data_size: u64,
+    ) -> Result<Self, ZipError> {
+        let overhead = decryptor.header_size() + decryptor.trailer_size();
+        let remaining = data_size.checked_sub(overhead).ok_or_else(|| {
+            ZipError::InvalidArchive("encrypted entry shorter than its header".to_string())
+        })?;
+        let mut header = vec![0u8; decryptor.header_size() as usize];
+        inner.read_exact(&mut header)?;
+        decryptor.check_header(&header)?;
+        Ok(Self {
+            inner,
+            decryptor,
+            remaining,
+            trailer_checked: false,
+        })
+    }
+
+    fn check_trailer(&mut self) -> io::Result<()> {
+        self.trailer_checked = true;
+        let mut trailer = vec![0u8; self.decryptor.trailer_size() as usize];
+        self.inner.read_exact(&mut trailer)?;
+        self.decryptor
+            .check_trailer(&trailer)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+}
+
+impl<R: Read> Read for DecryptionReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.remaining == 0 {
+            if !self.trailer_checked {
+                self.check_trailer()?;
+            }
+            return Ok(0);
+        }
+        let limit = buf.len().min(self.remaining as usize);
+        let n = self.inner.read(&mut buf[..limit])?;
+        if n == 0 && limit > 0 {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        self.decryptor.decrypt(&mut buf[..n]);
+        self.remaining -= n as u64;
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::aes::AesDecryptor;
+    use super::zipcrypt::ZipCryptoDecryptor;
+    use super::*;
+
+    fn decryptor(method: EncryptionMethod, password: &str, crc: u32) -> Box<dyn Decryptor> {
+        match method {
+            EncryptionMethod::ZipCrypto => Box::new(ZipCryptoDecryptor::new(password, crc)),
+            EncryptionMethod::Aes(strength, _) => {
+                Box::new(AesDecryptor::new(password.as_bytes(), strength))
+            }
+        }
+    }
+
+    #[test]
+    fn test_writer_reader_round_trip() {
+        let data = b"encrypted entry data\n".repeat(50);
+        let crc = crc32fast::hash(&data);
+        for method in [
+            EncryptionMethod::ZipCrypto,
+            EncryptionMethod::Aes(AesStrength::Aes256, AesVendorVersion::Ae2),
+        ] {
+            let mut writer =
+                EncryptionWriter::new(Vec::new(), Some("secret"), method, crc).unwrap();
+            let overhead = writer.overhead();
+            writer.write_all(&data).unwrap();
+            let encrypted = writer.finish().unwrap();
+            assert_eq!(encrypted.len() as u64, data.len() as u64 + overhead);
+
+            let mut output = Vec::new();
+            let size = encrypted.len() as u64;
+            DecryptionReader::new(&encrypted[..], decryptor(method, "secret", crc), size)
+                .unwrap()
+                .read_to_end(&mut output)
+                .unwrap();
+            assert_eq!(output, data);
+
+            // 记录的大小不足以容纳头部和尾部
+            let result = DecryptionReader::new(
+                &encrypted[..],
+                decryptor(method, "secret", crc),
+                overhead - 1,
+            );
+            assert!(matches!(
+                result,
+                Err(ZipError::InvalidArchive(message))
+                    if message == "encrypted entry shorter than its header"
+            ));
+
+            // 实际数据比记录的大小短：返回错误而不是panic
+            let short = &encrypted[..overhead as usize / 2];
+            assert!(DecryptionReader::new(short, decryptor(method, "secret", crc), size).is_err());
+            let short = &encrypted[..encrypted.len() - 20];
+            let result = DecryptionReader::new(short, decryptor(method, "secret", crc), size)
+                .and_then(|mut reader| Ok(reader.read_to_end(&mut Vec::new())?));
+            assert!(result.is_err());
+        }
+    }
+}
@@ -4,7 +4,9 @@
  * SPDX-License-Identifier: GPL-2.0-or-later
  */
 
+use super::{Decryptor, Encryptor};
 use crate::error::ZipError;
+use crate::zip::ZIP_CRYPTO_HEADER_SIZE;
 
 use std::fmt::{Debug, Formatter};
 use std::hash::Hash;
@@ -311,27 +313,55 @@ impl<W: Write> Write for ZipCryptoEncryptor<W> {
 
 impl<W: Write> ZipCryptoEncryptor<W> {
     pub fn finish(mut self) -> io::Result<W> {
+        // 空文件不会触发write，这里保证12字节加密头一定被写出
+        self.write_header()?;
         self.flush()?;
         Ok(self.inner)
     }
 }
 
+impl<W: Write> Encryptor<W> for ZipCryptoEncryptor<W> {
+    fn header_size(&self) -> u64 {
+        ZIP_CRYPTO_HEADER_SIZE
+    }
+
+    fn trailer_size(&self) -> u64 {
+        0
+    }
+
+    fn finish(self: Box<Self>) -> io::Result<W> {
+        ZipCryptoEncryptor::finish(*self)
+    }
+}
+
 /// 流式ZIP解密器
-#[allow(dead_code)]
 pub struct ZipCryptoDecryptor {
     keys: ZipCryptoKeys,
     header_read: bool,
-    expected_crc: u32,
+    // 加密头最后一个字节的期望值
+    check_byte: u8,
 }
 
 impl ZipCryptoDecryptor {
     /// 创建新的解密器
     #[allow(dead_code)]
     pub fn new(password: &str, expected_crc: u32) -> Self {
+        Self::with_validator(
+            password.as_bytes(),
+            ZipCryptoValidator::PkzipCrc32(expected_crc),
+        )
+    }
+
+    /// 按条目的校验方式创建解密器
+    pub fn with_validator(password: &[u8], validator: ZipCryptoValidator) -> Self {
+        let check_byte = match validator {
+            ZipCryptoValidator::PkzipCrc32(crc32) => (crc32 >> 24) as u8,
+            ZipCryptoValidator::InfoZipMsdosTime(last_mod_time) => (last_mod_time >> 8) as u8,
+        };
         Self {
-            keys: ZipCryptoKeys::derive(password.as_bytes()),
+            keys: ZipCryptoKeys::derive(password),
             header_read: false,
-            expected_crc,
+            check_byte,
         }
     }
 
@@ -339,45 +369,51 @@ impl ZipCryptoDecryptor {
     /// 如果是首次调用,会先处理12字节加密头并验证密码
     #[allow(dead_code)]
     pub fn decrypt_chunk(&mut self, data: &[u8]) -> std::io::Result<Vec<u8>> {
-        let mut result = Vec::new();
-
+        let mut data = data.to_vec();
         if !self.header_read {
             // 首块数据必须至少包含12字节头
-            if data.len() < 12 {
+            if (data.len() as u64) < ZIP_CRYPTO_HEADER_SIZE {
                 return Err(std::io::Error::new(
                     std::io::ErrorKind::InvalidData,
                     "First chunk too small for header",
                 ));
             }
+            let header: Vec<u8> = data.drain(..ZIP_CRYPTO_HEADER_SIZE as usize).collect();
+            self.check_header(&header).map_err(|_| {
+                std::io::Error::new(std::io::ErrorKind::InvalidData, "Invalid password")
+            })?;
+        }
+        self.decrypt(&mut data);
+        Ok(data)
+    }
+}
 
-            // 解密并验证头部
-            let mut header = [0u8; 12];
-            for i in 0..12 {
-                header[i] = self.keys.decrypt_byte(data[i]);
-            }
-
-            // 验证密码(使用CRC32的高字节)
-            if (self.expected_crc >> 24) as u8 != header[11] {
-                return Err(std::io::Error::new(
-                    std::io::ErrorKind::InvalidData,
-                    "Invalid password",
-                ));
-            }
+impl Decryptor for ZipCryptoDecryptor {
+    fn header_size(&self) -> u64 {
+        ZIP_CRYPTO_HEADER_SIZE
+    }
 
-            self.header_read = true;
+    fn trailer_size(&self) -> u64 {
+        0
+    }
 
-            // 解密剩余数据
-            for &byte in &data[12..] {
-                result.push(self.keys.decrypt_byte(byte));
-            }
-        } else {
-            // 非首块,直接解密所有数据
-            for &byte in data {
-                result.push(self.keys.decrypt_byte(byte));
-            }
+    fn check_header(&mut self, header: &[u8]) -> Result<(), ZipError> {
+        let mut last = 0;
+        for &byte in header {
+            last = self.keys.decrypt_byte(byte);
         }
+        self.header_read = true;
+        // 使用CRC32(或修改时间)的高字节校验密码
+        if last != self.check_byte {
+            return Err(ZipError::InvalidPassword);
+        }
+        Ok(())
+    }
 
-        Ok(result)
+    fn decrypt(&mut self, data: &mut [u8]) {
+        for byte in data.iter_mut() {
+            *byte = self.keys.decrypt_byte(*byte);
+        }
     }
 }
 
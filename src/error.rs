@@ -54,6 +54,42 @@ pub enum ZipError {
 
     #[error("utzip error: Zip file structure invalid ({0})")]
     InvalidArchive(String),
+
+    #[error("utzip error: CRC mismatch in {0} (expected {1:08x}, got {2:08x})")]
+    CrcMismatch(String, u32, u32),
+
+    #[error("utzip error: Entry name contains NUL byte ({0})")]
+    NulInEntryName(String),
+
+    #[error("utzip error: Absolute path in entry name ({0})")]
+    AbsoluteEntryPath(String),
+
+    #[error("utzip error: Path traversal in entry name ({0})")]
+    PathTraversal(String),
+
+    #[error("utzip error: Invalid entry name ({0})")]
+    InvalidEntryName(String),
+
+    #[error("utzip error: Symlink {0} points outside destination ({1})")]
+    SymlinkEscape(String, String),
+
+    #[error("utzip error: Path escapes destination directory ({0})")]
+    DestinationEscape(String),
+
+    #[error("utzip error: Resource limit exceeded ({0})")]
+    LimitExceeded(String),
+
+    #[error("utzip error: Overlapping entries ({0} overlaps {1})")]
+    OverlappingEntries(String, String),
+
+    #[error("utzip error: Authentication failed ({0})")]
+    AuthenticationFailed(String),
+
+    #[error("utzip error: Missing split volume ({0})")]
+    MissingVolume(String),
+
+    #[error("utzip error: Zip file test failed, original files unmodified ({0})")]
+    TestFailed(String),
 }
 
 #[derive(Error, Debug)]
@@ -81,6 +117,8 @@ pub enum ZipCloakError {
     ArchiveNotFound(String),
     #[error("utzipcloak error: Nothing to do! ({0})")]
     NothingToDo(String),
+    #[error("utzipcloak error: Password verification failed")]
+    PasswordMismatch,
     #[error("Pattern error: {0}")]
     PatternError(String),
 }
@@ -97,3 +135,230 @@ pub enum ZipSplitError {
     #[error("utzipsplit error: Entry too big to split, read, or write ({0})")]
     EntryTooLarge(String),
 }
+
+// 与 Info-ZIP zip 相同的返回值(ziperr.h 中的 ZE_*)，脚本依赖这些值判断失败原因
+pub const ZE_OK: i32 = 0; // 成功
+pub const ZE_EOF: i32 = 2; // 归档意外结束
+pub const ZE_FORM: i32 = 3; // 归档结构错误
+pub const ZE_MEM: i32 = 4; // 内存不足
+pub const ZE_LOGIC: i32 = 5; // 内部逻辑错误
+pub const ZE_BIG: i32 = 6; // 条目太大，无法拆分、读取或写入
+pub const ZE_NOTE: i32 = 7; // 注释格式错误
+pub const ZE_TEST: i32 = 8; // -T 检查失败
+pub const ZE_ABORT: i32 = 9; // 被中断
+pub const ZE_TEMP: i32 = 10; // 临时文件错误
+pub const ZE_READ: i32 = 11; // 读取失败或没有匹配的名称
+pub const ZE_NONE: i32 = 12; // 没有需要处理的内容
+pub const ZE_NAME: i32 = 13; // 归档不存在或为空
+pub const ZE_WRITE: i32 = 14; // 写入失败
+pub const ZE_CREAT: i32 = 15; // 无法创建文件
+pub const ZE_PARMS: i32 = 16; // 命令行参数错误
+pub const ZE_OPEN: i32 = 18; // 无法打开要读取的文件
+pub const ZE_COMPERR: i32 = 19; // 不支持的功能
+
+// utunzip 使用与 Info-ZIP unzip 相同的返回值(unzip.h 中的 PK_* 和 IZ_*)
+pub const PK_OK: i32 = 0; // 成功
+pub const PK_WARN: i32 = 1; // 有警告，但处理完成
+pub const PK_ERR: i32 = 2; // 部分条目处理失败
+pub const PK_BADERR: i32 = 3; // 严重的归档结构错误
+pub const PK_MEM: i32 = 4; // 内存不足
+pub const PK_NOZIP: i32 = 9; // 归档不存在或不是zip文件
+pub const PK_PARAM: i32 = 10; // 命令行参数错误
+pub const PK_FIND: i32 = 11; // 没有匹配的文件
+pub const PK_DISK: i32 = 50; // 磁盘已满等写入错误
+pub const PK_EOF: i32 = 51; // 归档意外结束
+pub const IZ_CTRLC: i32 = 80; // 被中断
+pub const IZ_UNSUP: i32 = 81; // 不支持的压缩方法或加密方式
+pub const IZ_BADPWD: i32 = 82; // 密码错误
+
+impl ZipError {
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            ZipError::Io(e) => io_exit_code(e),
+            ZipError::ArchiveNotFound(_) => ZE_NAME,
+            ZipError::EntryNotFound(_) | ZipError::PatternError(_) => ZE_READ,
+            ZipError::PasswordRequired
+            | ZipError::InvalidPassword
+            | ZipError::InvalidArguments(_)
+            | ZipError::InvalidDateTime(_)
+            | ZipError::DuplicateFileName(_) => ZE_PARMS,
+            ZipError::NothingToDo(_) => ZE_NONE,
+            ZipError::OperationNotPermitted(_) => ZE_CREAT,
+            ZipError::UnsupportedFeature(_) => ZE_COMPERR,
+            ZipError::Interrupted(_) => ZE_ABORT,
+            ZipError::LimitExceeded(_) => ZE_BIG,
+            ZipError::MissingVolume(_) => ZE_OPEN,
+            ZipError::TestFailed(_) => ZE_TEST,
+            ZipError::UnzipError(_)
+            | ZipError::InvalidArchive(_)
+            | ZipError::CrcMismatch(..)
+            | ZipError::NulInEntryName(_)
+            | ZipError::AbsoluteEntryPath(_)
+            | ZipError::PathTraversal(_)
+            | ZipError::InvalidEntryName(_)
+            | ZipError::SymlinkEscape(..)
+            | ZipError::DestinationEscape(_)
+            | ZipError::OverlappingEntries(..)
+            | ZipError::AuthenticationFailed(_) => ZE_FORM,
+        }
+    }
+
+    // utunzip 的返回值
+    pub fn unzip_exit_code(&self) -> i32 {
+        match self {
+            ZipError::Io(e) => unzip_io_exit_code(e),
+            ZipError::ArchiveNotFound(_) | ZipError::MissingVolume(_) => PK_NOZIP,
+            ZipError::EntryNotFound(_) | ZipError::PatternError(_) | ZipError::NothingToDo(_) => {
+                PK_FIND
+            }
+            ZipError::PasswordRequired
+            | ZipError::InvalidPassword
+            | ZipError::AuthenticationFailed(_) => IZ_BADPWD,
+            ZipError::InvalidArguments(_)
+            | ZipError::InvalidDateTime(_)
+            | ZipError::DuplicateFileName(_) => PK_PARAM,
+            ZipError::OperationNotPermitted(_) => PK_DISK,
+            ZipError::UnsupportedFeature(_) => IZ_UNSUP,
+            ZipError::Interrupted(_) => IZ_CTRLC,
+            ZipError::InvalidArchive(_)
+            | ZipError::LimitExceeded(_)
+            | ZipError::OverlappingEntries(..) => PK_BADERR,
+            ZipError::UnzipError(_)
+            | ZipError::TestFailed(_)
+            | ZipError::CrcMismatch(..)
+            | ZipError::NulInEntryName(_)
+            | ZipError::AbsoluteEntryPath(_)
+            | ZipError::PathTraversal(_)
+            | ZipError::InvalidEntryName(_)
+            | ZipError::SymlinkEscape(..)
+            | ZipError::DestinationEscape(_) => PK_ERR,
+        }
+    }
+}
+
+impl ZipNoteError {
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            ZipNoteError::InvalidArguments(_) => ZE_PARMS,
+            ZipNoteError::InvalidCommentFormat(_) => ZE_NOTE,
+            ZipNoteError::ArchiveNotFound(_) => ZE_NAME,
+            ZipNoteError::NothingToDo(_) => ZE_NONE,
+            ZipNoteError::PatternError(_) => ZE_READ,
+        }
+    }
+}
+
+impl ZipCloakError {
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            ZipCloakError::InvalidArguments(_) | ZipCloakError::PasswordMismatch => ZE_PARMS,
+            ZipCloakError::ArchiveNotFound(_) => ZE_NAME,
+            ZipCloakError::NothingToDo(_) => ZE_NONE,
+            ZipCloakError::PatternError(_) => ZE_READ,
+        }
+    }
+}
+
+impl ZipSplitError {
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            ZipSplitError::InvalidArguments(_) => ZE_PARMS,
+            ZipSplitError::ArchiveNotFound(_) => ZE_NAME,
+            ZipSplitError::NothingToDo(_) => ZE_NONE,
+            ZipSplitError::EntryTooLarge(_) => ZE_BIG,
+        }
+    }
+}
+
+fn io_exit_code(error: &std::io::Error) -> i32 {
+    use std::io::ErrorKind;
+    match error.kind() {
+        ErrorKind::UnexpectedEof => ZE_EOF,
+        ErrorKind::NotFound | ErrorKind::PermissionDenied => ZE_OPEN,
+        ErrorKind::OutOfMemory => ZE_MEM,
+        ErrorKind::WriteZero => ZE_WRITE,
+        ErrorKind::Interrupted => ZE_ABORT,
+        _ => ZE_READ,
+    }
+}
+
+fn unzip_io_exit_code(error: &std::io::Error) -> i32 {
+    use std::io::ErrorKind;
+    match error.kind() {
+        ErrorKind::UnexpectedEof => PK_EOF,
+        ErrorKind::NotFound => PK_NOZIP,
+        ErrorKind::OutOfMemory => PK_MEM,
+        ErrorKind::WriteZero | ErrorKind::PermissionDenied => PK_DISK,
+        ErrorKind::Interrupted => IZ_CTRLC,
+        _ => PK_ERR,
+    }
+}
+
+// 按错误链中第一个可识别的错误得到返回值，无法识别时返回 ZE_LOGIC
+pub fn exit_code(error: &anyhow::Error) -> i32 {
+    for cause in error.chain() {
+        if let Some(e) = cause.downcast_ref::<ZipError>() {
+            return e.exit_code();
+        }
+        if let Some(e) = cause.downcast_ref::<ZipNoteError>() {
+            return e.exit_code();
+        }
+        if let Some(e) = cause.downcast_ref::<ZipCloakError>() {
+            return e.exit_code();
+        }
+        if let Some(e) = cause.downcast_ref::<ZipSplitError>() {
+            return e.exit_code();
+        }
+        if let Some(e) = cause.downcast_ref::<std::io::Error>() {
+            return io_exit_code(e);
+        }
+    }
+    ZE_LOGIC
+}
+
+// utunzip 按错误链得到 PK_* 返回值，无法识别时返回 PK_ERR
+pub fn unzip_exit_code(error: &anyhow::Error) -> i32 {
+    for cause in error.chain() {
+        if let Some(e) = cause.downcast_ref::<ZipError>() {
+            return e.unzip_exit_code();
+        }
+        if let Some(e) = cause.downcast_ref::<std::io::Error>() {
+            return unzip_io_exit_code(e);
+        }
+    }
+    PK_ERR
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exit_code() {
+        let error = anyhow::Error::from(ZipError::NothingToDo("a.zip".to_string()));
+        assert_eq!(exit_code(&error), ZE_NONE);
+        let error = error.context("adding files");
+        assert_eq!(exit_code(&error), ZE_NONE);
+
+        let error = anyhow::Error::from(ZipSplitError::EntryTooLarge("big".to_string()));
+        assert_eq!(exit_code(&error), ZE_BIG);
+        let io = std::io::Error::new(std::io::ErrorKind::UnexpectedEof, "eof");
+        assert_eq!(exit_code(&anyhow::Error::from(ZipError::Io(io))), ZE_EOF);
+        let io = std::io::Error::from(std::io::ErrorKind::NotFound);
+        assert_eq!(exit_code(&anyhow::Error::from(io)), ZE_OPEN);
+        assert_eq!(exit_code(&anyhow::anyhow!("unknown")), ZE_LOGIC);
+    }
+
+    #[test]
+    fn test_unzip_exit_code() {
+        let error = anyhow::Error::from(ZipError::ArchiveNotFound("a.zip".into()));
+        assert_eq!(unzip_exit_code(&error.context("opening")), PK_NOZIP);
+        let error = anyhow::Error::from(ZipError::InvalidPassword);
+        assert_eq!(unzip_exit_code(&error), IZ_BADPWD);
+        let error = anyhow::Error::from(ZipError::UnsupportedFeature("method 99".to_string()));
+        assert_eq!(unzip_exit_code(&error), IZ_UNSUP);
+        let io = std::io::Error::new(std::io::ErrorKind::UnexpectedEof, "eof");
+        assert_eq!(unzip_exit_code(&anyhow::Error::from(io)), PK_EOF);
+        assert_eq!(unzip_exit_code(&anyhow::anyhow!("unknown")), PK_ERR);
+    }
+}
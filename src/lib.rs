@@ -5,8 +5,13 @@
  */
 
 pub mod cli;
+pub mod command;
+pub mod compression;
 pub mod encryption;
 pub mod error;
+pub mod unzip;
 pub mod utils;
 pub mod zip;
+pub mod zipcloak;
+pub mod zipnote;
 pub mod zipsplit;
@@ -4,11 +4,39 @@
  * SPDX-License-Identifier: GPL-2.0-or-later
  */
 
-mod cli;
-mod encryption;
-mod error;
-mod utils;
-mod zip;
+use log::LevelFilter;
+use utzip::cli;
+use utzip::command;
+use utzip::error;
+use utzip::utils::log::LogConfig;
+
 fn main() {
-    println!("Hello, world!");
+    let args = cli::parse_args();
+    if args.zipfile.is_none() {
+        // 与原生zip一致，单独的 -v 显示版本信息
+        if args.basic_options.verbose {
+            cli::show_version();
+        } else {
+            cli::show_help();
+        }
+        return;
+    }
+    if args.other.show_command {
+        println!("command line: {:?}", std::env::args().collect::<Vec<_>>());
+    }
+
+    let level = if args.other.show_debug {
+        LevelFilter::Debug
+    } else {
+        LevelFilter::Warn
+    };
+    LogConfig::init_logger(
+        args.basic_options.quiet || command::is_stdout(args.zipfile.as_deref()),
+        args.basic_options.verbose,
+        level,
+    );
+    if let Err(e) = command::run(args) {
+        eprintln!("{:#}", e);
+        std::process::exit(error::exit_code(&e));
+    }
 }
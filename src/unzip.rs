@@ -0,0 +1,501 @@
+/*
+ * SPDX-FileCopyrightText: 2025 UnionTech Software Technology Co., Ltd.
+ *
+ * SPDX-License-Identifier: GPL-2.0-or-later
+ */
+
+// 解压引擎：根据ZipArchive还原目录、文件和符号链接
+use crate::error::ZipError;
+use crate::utils::common::match_pattern;
+use crate::utils::log::LogConfig;
+use crate::utils::sanitize::{
+    check_symlink_target, ensure_within_root, printable_entry_name, safe_join, sanitize_entry_name,
+};
+use crate::zip::{CompressionMethod, ZipArchive, ZipFile};
+use anyhow::{Context, Result};
+use filetime::FileTime;
+use std::fs::{self, File};
+use std::io::{self, BufRead, Write};
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+
+// 目标文件已存在时的处理策略
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum OverwritePolicy {
+    // 逐个询问用户
+    #[default]
+    Prompt,
+    // 总是覆盖
+    Always,
+    // 从不覆盖，跳过该条目
+    Never,
+    // 解压为新的文件名
+    Rename,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ExtractOptions {
+    pub dest: PathBuf,
+    pub overwrite: OverwritePolicy,
+    pub include: Vec<String>,
+    pub exclude: Vec<String>,
+    pub no_wildcards: bool,
+    pub junk_paths: bool,
+    pub password: Option<String>,
+    pub restore_times: bool,
+    pub restore_permissions: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct ExtractSummary {
+    pub extracted: usize,
+    pub skipped: usize,
+    pub failed: usize,
+}
+
+// 目录的权限和时间需要在其中的文件解压完成后再设置
+struct PendingDir {
+    path: PathBuf,
+    mode: Option<u32>,
+    mtime: Option<FileTime>,
+}
+
+pub struct ZipExtractor {
+    archive: ZipArchive,
+    options: ExtractOptions,
+    pending_dirs: Vec<PendingDir>,
+}
+
+impl ZipExtractor {
+    pub fn new(archive: ZipArchive, options: ExtractOptions) -> Self {
+        Self {
+            archive,
+            options,
+            pending_dirs: Vec::new(),
+        }
+    }
+
+    pub fn archive(&self) -> &ZipArchive {
+        &self.archive
+    }
+
+    // 条目是否被包含/排除模式选中
+    pub fn is_selected(&self, name: &str) -> bool {
+        let no_wildcards = self.options.no_wildcards;
+        let included = self.options.include.is_empty()
+            || self
+                .options
+                .include
+                .iter()
+                .any(|p| match_pattern(name, p, no_wildcards));
+        included
+            && !self
+                .options
+                .exclude
+                .iter()
+                .any(|p| match_pattern(name, p, no_wildcards))
+    }
+
+    // 解压所有选中的条目，单个条目失败不影响其余条目
+    pub fn extract(&mut self) -> Result<ExtractSummary> {
+        let mut summary = ExtractSummary::default();
+        fs::create_dir_all(&self.options.dest).with_context(|| {
+            format!(
+                "cannot create extraction directory {}",
+                self.options.dest.display()
+            )
+        })?;
+
+        for index in 0..self.archive.len() {
+            let file = self.archive.by_index_raw(index)?;
+            let name = file.name();
+            if !self.is_selected(&name) {
+                continue;
+            }
+            match self.extract_entry(&file) {
+                Ok(true) => summary.extracted += 1,
+                Ok(false) => summary.skipped += 1,
+                Err(e) => {
+                    log::error!("{}: {}", name, e);
+                    summary.failed += 1;
+                }
+            }
+        }
+
+        self.apply_pending_dirs();
+        Ok(summary)
+    }
+
+    // 返回true表示已解压，false表示被跳过
+    fn extract_entry(&mut self, file: &ZipFile) -> Result<bool> {
+        let name = file.name();
+        let Some(path) = self.output_path(&name)? else {
+            return Ok(false);
+        };
+        ensure_within_root(&self.options.dest, &path)?;
+
+        if file.is_dir() {
+            if self.options.junk_paths {
+                return Ok(false);
+            }
+            if !path.is_dir() {
+                LogConfig::println(&format!("   creating: {}", path.display()));
+            }
+            fs::create_dir_all(&path)
+                .with_context(|| format!("cannot create directory {}", path.display()))?;
+            self.pending_dirs.push(PendingDir {
+                path,
+                mode: file.unix_mode(),
+                mtime: self.entry_mtime(file),
+            });
+            return Ok(true);
+        }
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("cannot create directory {}", parent.display()))?;
+        }
+
+        let Some(path) = self.resolve_existing(path)? else {
+            LogConfig::println(&format!("  skipping: {}", name));
+            return Ok(false);
+        };
+
+        if file.is_symlink() {
+            self.create_symlink(file, &path)?;
+        } else {
+            let action = match file.compression() {
+                CompressionMethod::Stored => " extracting",
+                CompressionMethod::Shrunk => "unshrinking",
+                CompressionMethod::Reduced(_) => " unreducing",
+                CompressionMethod::Imploded => "  exploding",
+                _ => "  inflating",
+            };
+            LogConfig::println(&format!("{}: {}", action, path.display()));
+            self.write_file(file, &path)?;
+        }
+        self.apply_metadata(file, &path)?;
+        Ok(true)
+    }
+
+    // 计算条目在目标目录中的路径，不安全的名称返回错误
+    fn output_path(&self, name: &str) -> Result<Option<PathBuf>, ZipError> {
+        if !self.options.junk_paths {
+            return safe_join(&self.options.dest, name).map(Some);
+        }
+        let sanitized = sanitize_entry_name(name)?;
+        if sanitized.ends_with('/') {
+            return Ok(None);
+        }
+        let file_name = sanitized.rsplit('/').next().unwrap_or(&sanitized);
+        Ok(Some(self.options.dest.join(file_name)))
+    }
+
+    // 按策略处理已存在的目标，返回None表示跳过
+    fn resolve_existing(&mut self, path: PathBuf) -> Result<Option<PathBuf>> {
+        if fs::symlink_metadata(&path).is_err() {
+            return Ok(Some(path));
+        }
+        if path.is_dir() && !path.is_symlink() {
+            return Err(ZipError::OperationNotPermitted(format!(
+                "{} exists and is a directory",
+                path.display()
+            ))
+            .into());
+        }
+
+        match self.options.overwrite {
+            OverwritePolicy::Always => Ok(Some(path)),
+            OverwritePolicy::Never => Ok(None),
+            OverwritePolicy::Rename => Ok(Some(unique_path(&path))),
+            OverwritePolicy::Prompt => self.prompt_overwrite(path),
+        }
+    }
+
+    fn prompt_overwrite(&mut self, path: PathBuf) -> Result<Option<PathBuf>> {
+        let stdin = io::stdin();
+        loop {
+            print!(
+                "replace {}? [y]es, [n]o, [A]ll, [N]one, [r]ename: ",
+                path.display()
+            );
+            io::stdout().flush()?;
+
+            let mut answer = String::new();
+            // 输入结束时按不覆盖处理
+            if stdin.lock().read_line(&mut answer)? == 0 {
+                println!();
+                return Ok(None);
+            }
+            match answer.trim() {
+                "y" | "Y" => return Ok(Some(path)),
+                "n" => return Ok(None),
+                "A" => {
+                    self.options.overwrite = OverwritePolicy::Always;
+                    return Ok(Some(path));
+                }
+                "N" => {
+                    self.options.overwrite = OverwritePolicy::Never;
+                    return Ok(None);
+                }
+                "r" | "R" => {
+                    print!("new name: ");
+                    io::stdout().flush()?;
+                    let mut new_name = String::new();
+                    stdin.lock().read_line(&mut new_name)?;
+                    let new_name = new_name.trim();
+                    if new_name.is_empty() {
+                        continue;
+                    }
+                    let renamed = path.with_file_name(new_name);
+                    return self.resolve_existing(renamed);
+                }
+                other => println!("error:  invalid response [{}]", other),
+            }
+        }
+    }
+
+    fn write_file(&self, file: &ZipFile, path: &Path) -> Result<()> {
+        let password = self.options.password.as_deref().map(str::as_bytes);
+        let mut reader = file.reader(password)?;
+
+        // 先移除旧文件，避免通过已存在的符号链接写到别处
+        if path.is_symlink() {
+            fs::remove_file(path)?;
+        }
+        let mut out =
+            File::create(path).with_context(|| format!("cannot create {}", path.display()))?;
+        if let Err(e) = io::copy(&mut reader, &mut out) {
+            drop(out);
+            let _ = fs::remove_file(path);
+            return Err(e.into());
+        }
+        Ok(())
+    }
+
+    fn create_symlink(&self, file: &ZipFile, path: &Path) -> Result<()> {
+        let password = self.options.password.as_deref().map(str::as_bytes);
+        let mut target = String::new();
+        io::Read::read_to_string(&mut file.reader(password)?, &mut target)?;
+        check_symlink_target(&self.options.dest, path, &target)?;
+
+        LogConfig::println(&format!("    linking: {} -> {}", path.display(), target));
+        if fs::symlink_metadata(path).is_ok() {
+            fs::remove_file(path)?;
+        }
+        std::os::unix::fs::symlink(&target, path)
+            .with_context(|| format!("cannot create symlink {}", path.display()))?;
+        Ok(())
+    }
+
+    fn apply_metadata(&self, file: &ZipFile, path: &Path) -> Result<()> {
+        let is_symlink = file.is_symlink();
+        if self.options.restore_times {
+            if let Some(mtime) = self.entry_mtime(file) {
+                if is_symlink {
+                    filetime::set_symlink_file_times(path, mtime, mtime)?;
+                } else {
+                    filetime::set_file_mtime(path, mtime)?;
+                }
+            }
+        }
+        // 符号链接本身没有权限位
+        if self.options.restore_permissions && !is_symlink {
+            if let Some(mode) = file.unix_mode() {
+                fs::set_permissions(path, fs::Permissions::from_mode(permission_bits(mode)))?;
+            }
+        }
+        Ok(())
+    }
+
+    fn apply_pending_dirs(&mut self) {
+        // 先处理深层目录，避免父目录变为只读后无法修改子目录
+        let mut dirs = std::mem::take(&mut self.pending_dirs);
+        dirs.sort_by_key(|d| std::cmp::Reverse(d.path.components().count()));
+        for dir in dirs {
+            if self.options.restore_permissions {
+                if let Some(mode) = dir.mode {
+                    let perms = fs::Permissions::from_mode(permission_bits(mode));
+                    if let Err(e) = fs::set_permissions(&dir.path, perms) {
+                        log::warn!("cannot set permissions on {}: {}", dir.path.display(), e);
+                    }
+                }
+            }
+            if self.options.restore_times {
+                if let Some(mtime) = dir.mtime {
+                    if let Err(e) = filetime::set_file_mtime(&dir.path, mtime) {
+                        log::warn!("cannot set times on {}: {}", dir.path.display(), e);
+                    }
+                }
+            }
+        }
+    }
+
+    // 优先使用UT额外字段中的UTC时间，否则使用DOS时间
+    fn entry_mtime(&self, file: &ZipFile) -> Option<FileTime> {
+        let secs = match file.ut_modification_time() {
+            Some(secs) => secs,
+            None => file.last_modified().ok()?.timestamp(),
+        };
+        Some(FileTime::from_unix_time(secs, 0))
+    }
+
+    // 校验选中条目的数据完整性
+    pub fn test(&self) -> Result<ExtractSummary> {
+        let mut summary = ExtractSummary::default();
+        let password = self.options.password.as_deref().map(str::as_bytes);
+
+        for index in 0..self.archive.len() {
+            let file = self.archive.by_index_raw(index)?;
+            let name = file.name();
+            if !self.is_selected(&name) {
+                continue;
+            }
+            let result = file
+                .reader(password)
+                .map_err(anyhow::Error::from)
+                .and_then(|mut reader| Ok(io::copy(&mut reader, &mut io::sink())?));
+            match result {
+                Ok(_) => {
+                    LogConfig::println(&format!("    testing: {:<40} OK", name));
+                    summary.extracted += 1;
+                }
+                Err(e) => {
+                    LogConfig::println(&format!("    testing: {:<40} FAILED", name));
+                    log::error!("{}: {}", name, e);
+                    summary.failed += 1;
+                }
+            }
+        }
+        Ok(summary)
+    }
+
+    // 简要列出选中的条目
+    pub fn list(&self) -> Result<()> {
+        println!("  Length      Date    Time    Name");
+        println!("---------  ---------- -----   ----");
+        let mut total_size = 0u64;
+        let mut count = 0usize;
+        for index in 0..self.archive.len() {
+            let file = self.archive.by_index_raw(index)?;
+            let name = file.name();
+            if !self.is_selected(&name) {
+                continue;
+            }
+            let name = match sanitize_entry_name(&name) {
+                Ok(name) => printable_entry_name(&name),
+                Err(e) => {
+                    log::warn!("{}", printable_entry_name(&e.to_string()));
+                    continue;
+                }
+            };
+            let modified = file
+                .last_modified()
+                .map(|t| t.format("%Y-%m-%d %H:%M").to_string())
+                .unwrap_or_else(|_| "1980-00-00 00:00".to_string());
+            println!("{:>9}  {}   {}", file.origin_size(), modified, name);
+            total_size += file.origin_size();
+            count += 1;
+        }
+        println!("---------                     -------");
+        println!(
+            "{:>9}                     {} file{}",
+            total_size,
+            count,
+            if count == 1 { "" } else { "s" }
+        );
+        Ok(())
+    }
+}
+
+// 去掉setuid/setgid位，仅保留普通权限和粘滞位
+fn permission_bits(mode: u32) -> u32 {
+    mode & 0o1777
+}
+
+// 生成不冲突的文件名：name_1.ext、name_2.ext ...
+fn unique_path(path: &Path) -> PathBuf {
+    let stem = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let ext = path
+        .extension()
+        .map(|e| format!(".{}", e.to_string_lossy()))
+        .unwrap_or_default();
+    (1..)
+        .map(|n| path.with_file_name(format!("{}_{}{}", stem, n, ext)))
+        .find(|candidate| fs::symlink_metadata(candidate).is_err())
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::zip::{FileOptions, ZipWriter};
+
+    #[test]
+    fn test_extract_restores_files_and_policies() {
+        let dir = tempfile::tempdir().unwrap();
+        let base = dir.path().to_path_buf();
+        let zip_path = base.join("test.zip");
+
+        let mut writer = ZipWriter::new(zip_path.to_str().unwrap()).unwrap();
+        writer.add_directory("dir/", FileOptions::new()).unwrap();
+        let mut options = FileOptions::new();
+        options.external_attr = (0o100640 << 16) | 0x20;
+        writer.start_file("dir/a.txt", options).unwrap();
+        writer.write_all(b"hello").unwrap();
+        writer.finish_file().unwrap();
+        writer
+            .start_file("../evil.txt", FileOptions::new())
+            .unwrap();
+        writer.write_all(b"evil").unwrap();
+        writer.finish_file().unwrap();
+        writer.start_file("skip.log", FileOptions::new()).unwrap();
+        writer.write_all(b"log").unwrap();
+        writer.finish_file().unwrap();
+        writer.finish().unwrap();
+
+        let dest = base.join("out");
+        let extract_options = ExtractOptions {
+            dest: dest.clone(),
+            overwrite: OverwritePolicy::Never,
+            exclude: vec!["*.log".to_string()],
+            restore_times: true,
+            restore_permissions: true,
+            ..Default::default()
+        };
+        let archive = ZipArchive::new(zip_path.to_str().unwrap()).unwrap();
+        let mut extractor = ZipExtractor::new(archive, extract_options.clone());
+        let summary = extractor.extract().unwrap();
+        assert_eq!(summary.extracted, 2);
+        assert_eq!(summary.failed, 1);
+        assert!(!base.join("evil.txt").exists());
+
+        let extracted = dest.join("dir/a.txt");
+        assert_eq!(fs::read(&extracted).unwrap(), b"hello");
+        assert_eq!(
+            fs::metadata(&extracted).unwrap().permissions().mode() & 0o777,
+            0o640
+        );
+        assert!(!dest.join("skip.log").exists());
+
+        // 已存在时：Never跳过，Rename生成新文件
+        let archive = ZipArchive::new(zip_path.to_str().unwrap()).unwrap();
+        let summary = ZipExtractor::new(archive, extract_options.clone())
+            .extract()
+            .unwrap();
+        assert_eq!(summary.skipped, 1);
+
+        let archive = ZipArchive::new(zip_path.to_str().unwrap()).unwrap();
+        let rename_options = ExtractOptions {
+            overwrite: OverwritePolicy::Rename,
+            ..extract_options
+        };
+        ZipExtractor::new(archive, rename_options)
+            .extract()
+            .unwrap();
+        assert_eq!(fs::read(dest.join("dir/a_1.txt")).unwrap(), b"hello");
+    }
+}
@@ -6,13 +6,13 @@
 
 use crate::cli;
 use crate::utils::logfile::LogFile;
+use crate::utils::sanitize::sanitize_entry_name;
 use crate::zip::{CompressionMethod, FileOptions, ZipArchive, ZipWriter};
-use anyhow::{Context, Result};
+use anyhow::Result;
 use chrono::{Datelike, Timelike};
 use log::{debug, warn};
-use std::fs::{self, File};
+use std::fs;
 use std::path::{Path, PathBuf};
-use std::time::SystemTime;
 
 // 跨文件系统安全的文件移动函数
 pub fn safe_move_file<P: AsRef<Path>, Q: AsRef<Path>>(from: P, to: Q) -> Result<()> {
@@ -84,7 +84,7 @@ pub fn safe_move_file<P: AsRef<Path>, Q: AsRef<Path>>(from: P, to: Q) -> Result<
 }
 
 // 生成类似标准zip工具的随机临时文件名
-fn generate_temp_filename() -> String {
+pub fn generate_temp_filename() -> String {
     use std::time::{SystemTime, UNIX_EPOCH};
 
     // 使用时间戳和进程ID来生成更加随机的文件名
@@ -98,6 +98,28 @@ fn generate_temp_filename() -> String {
     format!("zi{:06X}", (pid ^ timestamp_part) & 0xFFFFFF)
 }
 
+// 临时归档的路径：优先放在 -b 指定的目录，否则与目标归档在同一目录，便于最后原子替换
+pub fn temp_archive_path(target: &Path, temp_dir: Option<&Path>) -> PathBuf {
+    let dir = match temp_dir {
+        Some(dir) => dir.to_path_buf(),
+        None => target
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+            .map_or_else(|| PathBuf::from("."), Path::to_path_buf),
+    };
+    dir.join(generate_temp_filename())
+}
+
+// 从终端读取密码(不回显)，没有终端时从标准输入读取一行
+pub fn read_password(prompt: &str) -> Result<String> {
+    if let Ok(password) = rpassword::prompt_password(prompt) {
+        return Ok(password);
+    }
+    let mut line = String::new();
+    std::io::stdin().read_line(&mut line)?;
+    Ok(line.trim_end_matches(['\r', '\n']).to_string())
+}
+
 // 添加新的结构体来跟踪压缩信息
 pub struct FileCompressionTracker {
     pub original_size: u64,
@@ -109,10 +131,10 @@ pub struct FileCompressionTracker {
 }
 
 #[derive(Default)]
-pub struct RunState<'a> {
+pub struct RunState {
     pub zip_file: Option<PathBuf>,
     pub zip_file_tmp: Option<PathBuf>,
-    pub writer: Option<ZipWriter<'a>>,
+    pub writer: Option<ZipWriter>,
     pub archive: Option<ZipArchive>,
     pub file_options: FileOptions,
     pub dirs_to_remove: std::collections::HashSet<PathBuf>, // 待删除的目录
@@ -131,6 +153,7 @@ pub struct RunState<'a> {
     pub quiet: bool,      // 启用安静模式
     pub show_debug: bool, // 启用调试模式 (--sd)
 
+    #[allow(dead_code)]
     output: Option<PathBuf>, // 输出文件路径
 
     pub log_file: Option<LogFile>, // 日志文件
@@ -147,19 +170,21 @@ pub struct RunState<'a> {
     pub disk_num: u16,
     pub changed_files_count: u16,
     pub changed_files_size: u64,
+    #[allow(dead_code)]
     last_changed_file_size: u64,
     pub changed_files_total_size: u64,
     pub changed_files_total_count: u16,
 
     pub args: cli::ZipArgs,
 
+    #[allow(dead_code)]
     global_bytes_processed: u64,
     #[allow(dead_code)]
     global_dots_shown: u64,
 }
 
 // 手动实现Debug，跳过writer和archive字段
-impl<'a> std::fmt::Debug for RunState<'a> {
+impl std::fmt::Debug for RunState {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("RunState")
             .field("zip_file", &self.zip_file)
@@ -177,7 +202,7 @@ impl<'a> std::fmt::Debug for RunState<'a> {
     }
 }
 
-impl<'a> RunState<'a> {
+impl RunState {
     pub fn new(zipfile: Option<PathBuf>) -> Self {
         // 初始化RunState
         let zip_file = zipfile;
@@ -334,16 +359,20 @@ pub fn get_file_modification_time(file_path: &Path) -> anyhow::Result<(u16, u16)
     // 将SystemTime转换为chrono::DateTime
     let modified = chrono::DateTime::<chrono::Local>::from(modified);
 
-    // 转换为ZIP格式时间戳
-    let time = ((modified.hour() as u16) << 11)    // 小时占5位(11-15)
-             | ((modified.minute() as u16) << 5)   // 分钟占6位(5-10)
-             | ((modified.second() as u16) >> 1); // 秒/2占5位(0-4)
+    Ok(datetime_to_dos(&modified))
+}
+
+// 将本地时间转换为ZIP格式(MS-DOS)的时间和日期
+pub fn datetime_to_dos(datetime: &chrono::DateTime<chrono::Local>) -> (u16, u16) {
+    let time = ((datetime.hour() as u16) << 11)    // 小时占5位(11-15)
+             | ((datetime.minute() as u16) << 5)   // 分钟占6位(5-10)
+             | ((datetime.second() as u16) >> 1); // 秒/2占5位(0-4)
 
-    let date = (((modified.year() - 1980) as u16) << 9)  // 年从1980开始，占7位(9-15)
-             | ((modified.month() as u16) << 5)          // 月占4位(5-8)
-             | (modified.day() as u16); // 日占5位(0-4)
+    let date = (((datetime.year() - 1980) as u16) << 9)  // 年从1980开始，占7位(9-15)
+             | ((datetime.month() as u16) << 5)          // 月占4位(5-8)
+             | (datetime.day() as u16); // 日占5位(0-4)
 
-    Ok((time, date))
+    (time, date)
 }
 
 // 简单的模式匹配函数
@@ -593,8 +622,17 @@ pub fn search_pattern_in_archive(
             if regex.is_match(&name) {
                 debug!("Found matching entry in archive: {}", name);
 
+                // 不安全的条目名称不能用于访问文件系统
+                let local_name = match sanitize_entry_name(&name) {
+                    Ok(local_name) => local_name,
+                    Err(e) => {
+                        warn!("{}", e);
+                        continue;
+                    }
+                };
+
                 // 检查文件系统中是否存在对应的文件
-                if std::path::Path::new(&name).exists() {
+                if std::path::Path::new(&local_name).exists() {
                     // 只有文件系统中也存在的文件才会被处理，符合原生zip的行为
                     matched_files.insert(
                         name.clone(),
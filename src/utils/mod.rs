@@ -7,3 +7,4 @@
 pub mod common;
 pub mod log;
 pub mod logfile;
+pub mod sanitize;
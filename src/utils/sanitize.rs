@@ -0,0 +1,177 @@
+/*
+ * SPDX-FileCopyrightText: 2025 UnionTech Software Technology Co., Ltd.
+ *
+ * SPDX-License-Identifier: GPL-2.0-or-later
+ */
+
+// 条目名称安全检查：所有要落到文件系统或展示给用户的条目名称都必须经过这里
+use crate::error::ZipError;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+// 将条目名称规范化为以'/'分隔的相对路径，目录保留结尾的'/'
+// 反斜杠、重复的'/'和'.'会被改写，NUL、绝对路径和'..'直接拒绝
+pub fn sanitize_entry_name(name: &str) -> Result<String, ZipError> {
+    if name.contains('\0') {
+        return Err(ZipError::NulInEntryName(name.escape_default().to_string()));
+    }
+
+    let unified = name.replace('\\', "/");
+    if unified.starts_with('/') || has_drive_prefix(&unified) {
+        return Err(ZipError::AbsoluteEntryPath(name.to_string()));
+    }
+
+    let mut parts = Vec::new();
+    for part in unified.split('/') {
+        match part {
+            "" | "." => continue,
+            ".." => return Err(ZipError::PathTraversal(name.to_string())),
+            part => parts.push(part),
+        }
+    }
+    if parts.is_empty() {
+        return Err(ZipError::InvalidEntryName(name.to_string()));
+    }
+
+    let mut sanitized = parts.join("/");
+    if unified.ends_with('/') {
+        sanitized.push('/');
+    }
+    Ok(sanitized)
+}
+
+// 显示用的条目名称：与原生unzip一致，控制字符显示为 ^X 形式，避免名称中的转义序列作用于终端
+pub fn printable_entry_name(name: &str) -> String {
+    let mut printable = String::with_capacity(name.len());
+    for c in name.chars() {
+        match c {
+            '\0'..='\x1f' | '\x7f' => {
+                printable.push('^');
+                printable.push((c as u8 ^ 0x40) as char);
+            }
+            c if c.is_control() => printable.extend(c.escape_unicode()),
+            c => printable.push(c),
+        }
+    }
+    printable
+}
+
+// Windows盘符前缀，例如 C:/
+fn has_drive_prefix(name: &str) -> bool {
+    let bytes = name.as_bytes();
+    bytes.len() >= 2 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':'
+}
+
+// 将条目名称拼接到目标根目录下
+pub fn safe_join(root: &Path, name: &str) -> Result<PathBuf, ZipError> {
+    let sanitized = sanitize_entry_name(name)?;
+    Ok(root.join(sanitized.trim_end_matches('/')))
+}
+
+// 确认路径中已存在的部分没有经由符号链接逃出根目录，应在创建目录或写文件前调用
+pub fn ensure_within_root(root: &Path, path: &Path) -> Result<(), ZipError> {
+    let root = root.canonicalize()?;
+    // path本身若为符号链接会在写入前被替换，因此只检查其上级目录
+    let mut existing = path.parent();
+    while let Some(dir) = existing {
+        if fs::symlink_metadata(dir).is_ok() {
+            let resolved = dir.canonicalize()?;
+            if !resolved.starts_with(&root) {
+                return Err(ZipError::DestinationEscape(path.display().to_string()));
+            }
+            return Ok(());
+        }
+        existing = dir.parent();
+    }
+    Ok(())
+}
+
+// 检查符号链接目标解析后仍在根目录内
+// 目标只允许以若干'..'开头，之后不能再出现'..'，避免经由其他链接绕过词法检查
+pub fn check_symlink_target(root: &Path, link: &Path, target: &str) -> Result<(), ZipError> {
+    let escape = || ZipError::SymlinkEscape(link.display().to_string(), target.to_string());
+    if target.is_empty() || target.contains('\0') {
+        return Err(escape());
+    }
+
+    let mut resolved = match link.parent() {
+        Some(parent) => parent.canonicalize()?,
+        None => return Err(escape()),
+    };
+    let mut descended = false;
+    for component in Path::new(target).components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir if !descended => {
+                resolved.pop();
+            }
+            Component::Normal(part) => {
+                descended = true;
+                resolved.push(part);
+            }
+            _ => return Err(escape()),
+        }
+    }
+
+    if resolved.starts_with(root.canonicalize()?) {
+        Ok(())
+    } else {
+        Err(escape())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sanitize_entry_name() {
+        assert_eq!(sanitize_entry_name("a/./b//c.txt").unwrap(), "a/b/c.txt");
+        assert_eq!(sanitize_entry_name("dir\\sub\\").unwrap(), "dir/sub/");
+        assert!(matches!(
+            sanitize_entry_name("a/../../etc/passwd"),
+            Err(ZipError::PathTraversal(_))
+        ));
+        assert!(matches!(
+            sanitize_entry_name("..\\evil"),
+            Err(ZipError::PathTraversal(_))
+        ));
+        assert!(matches!(
+            sanitize_entry_name("/etc/passwd"),
+            Err(ZipError::AbsoluteEntryPath(_))
+        ));
+        assert!(matches!(
+            sanitize_entry_name("C:\\Windows"),
+            Err(ZipError::AbsoluteEntryPath(_))
+        ));
+        assert!(matches!(
+            sanitize_entry_name("a\0b"),
+            Err(ZipError::NulInEntryName(_))
+        ));
+        assert!(matches!(
+            sanitize_entry_name("./"),
+            Err(ZipError::InvalidEntryName(_))
+        ));
+        assert_eq!(
+            printable_entry_name("a\x1b[2J\x7f\u{9b}文件.txt"),
+            "a^[[2J^?\\u{9b}文件.txt"
+        );
+    }
+
+    #[test]
+    fn test_check_symlink_target() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().to_path_buf();
+        fs::create_dir_all(root.join("sub")).unwrap();
+        let link = root.join("sub/link");
+
+        assert!(check_symlink_target(&root, &link, "../other/file").is_ok());
+        assert!(check_symlink_target(&root, &link, "sibling").is_ok());
+        for target in ["../../outside", "/etc/passwd", "x/../../.."] {
+            assert!(matches!(
+                check_symlink_target(&root, &link, target),
+                Err(ZipError::SymlinkEscape(_, _))
+            ));
+        }
+    }
+}
@@ -4,24 +4,36 @@
  * SPDX-License-Identifier: GPL-2.0-or-later
  */
 
+use bzip2::read::BzDecoder;
 use bzip2::write::BzEncoder;
-use chrono::{Datelike, Local, TimeZone, Timelike};
+use chrono::{Local, TimeZone};
 use crc32fast::Hasher;
+use deflate64::Deflate64Decoder;
+use flate2::read::DeflateDecoder;
 use flate2::write::DeflateEncoder;
-use flate2::Compression;
 use std::collections::HashSet;
 use std::fs::{metadata, File};
 use std::io::Seek;
 use std::io::SeekFrom;
 use std::io::{self, Read, Write};
-use std::os::unix::io::AsRawFd;
 use std::path::Path;
 use std::path::PathBuf;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
+use xz2::write::XzEncoder;
 
-use crate::encryption::zipcrypt::{ZipCryptoDecryptor, ZipCryptoEncryptor};
+use crate::compression::implode::ImplodeDecoder;
+use crate::compression::lzma::{
+    finish_lzma_encoder, lzma_decoder, lzma_encoder, xz_decoder, LzmaHeaderWriter,
+};
+use crate::compression::reduce::ReduceDecoder;
+use crate::compression::shrink::ShrinkDecoder;
+use crate::encryption::aes::{AesDecryptor, AesExtraField, AesVendorVersion, AES_EXTRA_FIELD_ID};
+use crate::encryption::zipcrypt::{ZipCryptoDecryptor, ZipCryptoValidator};
+use crate::encryption::{DecryptionReader, Decryptor, EncryptionMethod, EncryptionWriter};
+use crate::error::ZipError;
 
-use crate::utils::common::get_file_modification_time;
+use crate::utils::common::{datetime_to_dos, get_file_modification_time};
+use crate::utils::log::LogConfig;
 
 pub const ZIP_CRYPTO_FLAG: u16 = 0x1;
 pub const VERSION_MADE: u16 = 0x031E; // 3.0 (Unix)
@@ -31,32 +43,108 @@ pub const VERSION_NEEDED_ZIP64: u16 = 0x2D; // 4.5 for ZIP64
 // ZIP64常量
 pub const ZIP64_VERSION_MADE: u16 = 0x032D; // 4.5 (Unix)
 pub const ZIP64_EXTRA_FIELD_ID: u16 = 0x0001; // ZIP64扩展信息额外字段标识符
-#[allow(dead_code)]
 pub const ZIP64_END_OF_CENTRAL_DIR_SIZE: usize = 56; // ZIP64结束目录记录大小
 pub const ZIP64_END_OF_CENTRAL_DIR_LOCATOR_SIZE: usize = 20; // ZIP64结束目录定位器大小
 pub const MAX_ZIP_SIZE: u32 = 0xFFFFFFFF; // 4GB - 1 (ZIP格式32位限制)
 pub const MAX_ZIP_ENTRIES: u16 = 0xFFFF; // 65535 (ZIP格式16位限制)
 
+// 记录签名与固定长度
+pub const LOCAL_FILE_HEADER_SIGNATURE: u32 = 0x04034b50;
+pub const CENTRAL_DIR_HEADER_SIGNATURE: u32 = 0x02014b50;
+pub const END_OF_CENTRAL_DIR_SIGNATURE: u32 = 0x06054b50;
+pub const ZIP64_END_OF_CENTRAL_DIR_LOCATOR_SIGNATURE: u32 = 0x07064b50;
+pub const END_OF_CENTRAL_DIR_SIZE: usize = 22; // 结束目录记录固定部分大小(不含注释)
+pub const CENTRAL_DIR_HEADER_SIZE: usize = 46; // 中央目录记录固定部分大小
+pub const MAX_COMMENT_SIZE: usize = 0xFFFF; // 归档注释最大长度
+pub const LOCAL_FILE_HEADER_SIZE: usize = 30; // 本地文件头固定部分大小
+pub const DATA_DESCRIPTOR_SIGNATURE: u32 = 0x08074b50;
+pub const DATA_DESCRIPTOR_FLAG: u16 = 0x8; // 通用标志位3：CRC和大小记录在数据描述符中
+pub const LZMA_EOS_FLAG: u16 = 0x2; // 通用标志位1：LZMA数据以结束标记结尾
+pub const ZIP64_END_OF_CENTRAL_DIR_SIGNATURE: u32 = 0x06064b50;
+pub const ZIP_CRYPTO_HEADER_SIZE: u64 = 12; // ZipCrypto加密头大小
+pub const UT_EXTRA_FIELD_ID: u16 = 0x5455; // 扩展时间戳(UT)额外字段标识符
+pub const SPLIT_SIGNATURE: u32 = 0x08074b50; // 分卷归档第一个分卷开头的签名
+pub const SINGLE_SEGMENT_SIGNATURE: u32 = 0x30304b50; // 分卷写入但只有一个分卷时的"PK00"标记
+const SPLIT_SIGNATURE_SIZE: u64 = 4;
+
+// Unix文件类型位(external_attr高16位)
+pub const UNIX_FILE_TYPE_MASK: u32 = 0o170000;
+pub const UNIX_SYMLINK_TYPE: u32 = 0o120000;
+const HOST_SYSTEM_UNIX: u16 = 3;
+
+// 自动切换Store模式时最多缓存的原始数据大小，超过后不再尝试切换
+const AUTO_STORE_BUFFER_LIMIT: usize = 4 * 1024 * 1024;
+
 // 压缩方法枚举
 #[derive(Default, Clone, Copy, Debug, PartialEq)]
 pub enum CompressionMethod {
     #[default]
-    Stored = 0,
-    Deflated = 8,
-    Bzip2 = 12,
+    Stored,
+    // 以下四种旧式方法只支持读取，Reduced携带压缩因子(1-4)
+    Shrunk,
+    Reduced(u8),
+    Imploded,
+    Deflated,
+    Deflate64,
+    Bzip2,
+    Lzma,
+    Zstd,
+    Xz,
+    // WinZip AES加密条目，实际压缩方法记录在0x9901额外字段中
+    Aes,
+    // 不支持的压缩方法，保留原始编号以便列出和原样复制
+    Unknown(u16),
 }
 
 impl CompressionMethod {
+    pub fn id(self) -> u16 {
+        match self {
+            Self::Stored => 0,
+            Self::Shrunk => 1,
+            Self::Reduced(factor) => 1 + factor as u16,
+            Self::Imploded => 6,
+            Self::Deflated => 8,
+            Self::Deflate64 => 9,
+            Self::Bzip2 => 12,
+            Self::Lzma => 14,
+            Self::Zstd => 93,
+            Self::Xz => 95,
+            Self::Aes => 99,
+            Self::Unknown(id) => id,
+        }
+    }
+
     pub fn to_le_bytes(self) -> [u8; 2] {
-        (self as u16).to_le_bytes()
+        self.id().to_le_bytes()
     }
 
     pub fn from(num: u16) -> Self {
         match num {
             0 => Self::Stored,
+            1 => Self::Shrunk,
+            2..=5 => Self::Reduced((num - 1) as u8),
+            6 => Self::Imploded,
             8 => Self::Deflated,
+            9 => Self::Deflate64,
             12 => Self::Bzip2,
-            _ => Self::Stored,
+            14 => Self::Lzma,
+            93 => Self::Zstd,
+            95 => Self::Xz,
+            99 => Self::Aes,
+            id => Self::Unknown(id),
+        }
+    }
+
+    // 命令行 -Z 参数中的方法名
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "store" => Some(Self::Stored),
+            "deflate" => Some(Self::Deflated),
+            "bzip2" => Some(Self::Bzip2),
+            "lzma" => Some(Self::Lzma),
+            "zstd" => Some(Self::Zstd),
+            "xz" => Some(Self::Xz),
+            _ => None,
         }
     }
 }
@@ -65,12 +153,27 @@ impl std::fmt::Display for CompressionMethod {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             CompressionMethod::Stored => write!(f, "stored"),
+            CompressionMethod::Shrunk => write!(f, "shrunk"),
+            CompressionMethod::Reduced(factor) => write!(f, "reduced{}", factor),
+            CompressionMethod::Imploded => write!(f, "imploded"),
             CompressionMethod::Deflated => write!(f, "deflated"),
+            CompressionMethod::Deflate64 => write!(f, "deflate64"),
             CompressionMethod::Bzip2 => write!(f, "bzipped"),
+            CompressionMethod::Lzma => write!(f, "lzma"),
+            CompressionMethod::Zstd => write!(f, "zstd"),
+            CompressionMethod::Xz => write!(f, "xz"),
+            CompressionMethod::Aes => write!(f, "aes"),
+            CompressionMethod::Unknown(id) => write!(f, "method {}", id),
         }
     }
 }
 
+// 将zip的 0-9 压缩级别映射到zstd级别，默认级别6对应zstd默认的3
+fn zstd_level(level: u32) -> i32 {
+    const LEVELS: [i32; 10] = [1, 1, 2, 2, 3, 3, 3, 6, 12, 19];
+    LEVELS[level.min(9) as usize]
+}
+
 // ZIP64结束目录记录
 #[derive(Debug, Clone)]
 pub struct Zip64EndOfCentralDir {
@@ -85,6 +188,24 @@ pub struct Zip64EndOfCentralDir {
     pub central_dir_offset: u64,
 }
 
+impl Zip64EndOfCentralDir {
+    // 序列化为ZIP64结束目录记录(包含签名)
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut data = Vec::with_capacity(ZIP64_END_OF_CENTRAL_DIR_SIZE);
+        data.extend_from_slice(&ZIP64_END_OF_CENTRAL_DIR_SIGNATURE.to_le_bytes());
+        data.extend_from_slice(&self.size_of_record.to_le_bytes());
+        data.extend_from_slice(&self.version_made.to_le_bytes());
+        data.extend_from_slice(&self.version_needed.to_le_bytes());
+        data.extend_from_slice(&self.disk_number.to_le_bytes());
+        data.extend_from_slice(&self.central_dir_disk.to_le_bytes());
+        data.extend_from_slice(&self.entries_on_disk.to_le_bytes());
+        data.extend_from_slice(&self.total_entries.to_le_bytes());
+        data.extend_from_slice(&self.central_dir_size.to_le_bytes());
+        data.extend_from_slice(&self.central_dir_offset.to_le_bytes());
+        data
+    }
+}
+
 impl Default for Zip64EndOfCentralDir {
     fn default() -> Self {
         Self {
@@ -102,15 +223,116 @@ impl Default for Zip64EndOfCentralDir {
 }
 
 // 压缩编码器枚举
-pub enum CompressionEncoder<W: Write + 'static> {
-    Stored(W),
-    Deflate(DeflateEncoder<W>),
-    Bzip2(BzEncoder<W>),
-    // 仅加密（无压缩）
-    Encrypted(ZipCryptoEncryptor<W>),
-    // 压缩+加密
-    DeflateEncrypted(DeflateEncoder<ZipCryptoEncryptor<W>>),
-    Bzip2Encrypted(BzEncoder<ZipCryptoEncryptor<W>>),
+// 压缩与加密是相互独立的两层：压缩编码器写入 EncryptionWriter，由它决定是否加密
+pub enum CompressionEncoder<W: Write> {
+    Stored(EncryptionWriter<W>),
+    Deflate(DeflateEncoder<EncryptionWriter<W>>),
+    Bzip2(BzEncoder<EncryptionWriter<W>>),
+    Zstd(zstd::stream::write::Encoder<'static, EncryptionWriter<W>>),
+    Lzma(XzEncoder<LzmaHeaderWriter<EncryptionWriter<W>>>),
+    Xz(XzEncoder<EncryptionWriter<W>>),
+}
+
+impl<W: Write> CompressionEncoder<W> {
+    // 根据压缩方法和密码创建编码器，encryption 指定设置密码时使用的加密方式
+    // ZipCrypto时 verifier 的最高字节会写入加密头用于校验密码(CRC32或者 修改时间<<16)
+    pub fn new(
+        writer: W,
+        method: CompressionMethod,
+        level: u32,
+        password: Option<&str>,
+        encryption: EncryptionMethod,
+        verifier: u32,
+    ) -> io::Result<Self> {
+        if let method @ (CompressionMethod::Shrunk
+        | CompressionMethod::Reduced(_)
+        | CompressionMethod::Imploded
+        | CompressionMethod::Deflate64
+        | CompressionMethod::Aes
+        | CompressionMethod::Unknown(_)) = method
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                ZipError::UnsupportedFeature(format!(
+                    "compression method {} for writing",
+                    method.id()
+                )),
+            ));
+        }
+
+        let writer = EncryptionWriter::new(writer, password, encryption, verifier)?;
+        let encoder = match method {
+            CompressionMethod::Deflated => Self::Deflate(DeflateEncoder::new(
+                writer,
+                flate2::Compression::new(level.min(9)),
+            )),
+            CompressionMethod::Bzip2 => Self::Bzip2(BzEncoder::new(
+                writer,
+                bzip2::Compression::new(level.clamp(1, 9)),
+            )),
+            CompressionMethod::Zstd => Self::Zstd(zstd::stream::write::Encoder::new(
+                writer,
+                zstd_level(level),
+            )?),
+            CompressionMethod::Lzma => Self::Lzma(lzma_encoder(writer, level)?),
+            CompressionMethod::Xz => Self::Xz(XzEncoder::new(writer, level.min(9))),
+            _ => Self::Stored(writer),
+        };
+        Ok(encoder)
+    }
+
+    fn encryption_writer(&self) -> &EncryptionWriter<W> {
+        match self {
+            Self::Stored(writer) => writer,
+            Self::Deflate(encoder) => encoder.get_ref(),
+            Self::Bzip2(encoder) => encoder.get_ref(),
+            Self::Zstd(encoder) => encoder.get_ref(),
+            Self::Lzma(encoder) => encoder.get_ref().get_ref(),
+            Self::Xz(encoder) => encoder.get_ref(),
+        }
+    }
+
+    // 加密层在压缩数据之外增加的字节数(加密头和尾部)
+    pub fn crypt_overhead(&self) -> u64 {
+        self.encryption_writer().overhead()
+    }
+
+    // 结束压缩/加密并返回底层写入器
+    pub fn finish(self) -> io::Result<W> {
+        let writer = match self {
+            Self::Stored(writer) => writer,
+            Self::Deflate(encoder) => encoder.finish()?,
+            Self::Bzip2(encoder) => encoder.finish()?,
+            Self::Zstd(encoder) => encoder.finish()?,
+            Self::Lzma(encoder) => finish_lzma_encoder(encoder)?,
+            Self::Xz(encoder) => encoder.finish()?,
+        };
+        writer.finish()
+    }
+}
+
+impl<W: Write> Write for CompressionEncoder<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Self::Stored(writer) => writer.write(buf),
+            Self::Deflate(encoder) => encoder.write(buf),
+            Self::Bzip2(encoder) => encoder.write(buf),
+            Self::Zstd(encoder) => encoder.write(buf),
+            Self::Lzma(encoder) => encoder.write(buf),
+            Self::Xz(encoder) => encoder.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Self::Stored(writer) => writer.flush(),
+            Self::Deflate(encoder) => encoder.flush(),
+            Self::Bzip2(encoder) => encoder.flush(),
+            Self::Zstd(encoder) => encoder.flush(),
+            Self::Lzma(encoder) => encoder.flush(),
+            Self::Xz(encoder) => encoder.flush(),
+        }
+    }
 }
 
 // ZIP64扩展信息结构
@@ -182,6 +404,43 @@ impl Zip64ExtendedInfo {
         data
     }
 
+    // 按照中央目录中哪些标准字段为最大值来解析ZIP64额外字段
+    // 规范要求只有被置为0xFFFFFFFF(磁盘号为0xFFFF)的字段才会出现在额外字段中，且顺序固定
+    pub fn from_extra_field(
+        data: &[u8],
+        uncompressed_max: bool,
+        compressed_max: bool,
+        offset_max: bool,
+        disk_max: bool,
+    ) -> anyhow::Result<Self> {
+        let mut info = Self::new();
+        let mut offset = 0;
+        let read_u64 = |offset: &mut usize| -> anyhow::Result<u64> {
+            let bytes = data
+                .get(*offset..*offset + 8)
+                .ok_or_else(|| ZipError::InvalidArchive("ZIP64 extra field too short".into()))?;
+            *offset += 8;
+            Ok(u64::from_le_bytes(bytes.try_into()?))
+        };
+        if uncompressed_max {
+            info.uncompressed_size = Some(read_u64(&mut offset)?);
+        }
+        if compressed_max {
+            info.compressed_size = Some(read_u64(&mut offset)?);
+        }
+        if offset_max {
+            info.local_header_offset = Some(read_u64(&mut offset)?);
+        }
+        if disk_max {
+            let bytes = data
+                .get(offset..offset + 4)
+                .ok_or_else(|| ZipError::InvalidArchive("ZIP64 extra field too short".into()))?;
+            info.disk_start_number = Some(u32::from_le_bytes(bytes.try_into()?));
+        }
+        Ok(info)
+    }
+
+    #[allow(dead_code)]
     pub fn from_bytes(data: &[u8]) -> anyhow::Result<Self> {
         let mut info = Self::new();
         let mut offset = 0;
@@ -219,6 +478,11 @@ pub struct ArchiveFileInfo {
     pub zip64_num_entries: Option<u64>,
     pub zip64_size: Option<u64>,
     pub zip64_offset: Option<u64>,
+    // 记录的偏移比实际位置少的字节数：自解压程序等前缀没有调整偏移(-A)时不为0
+    pub offset_shift: u64,
+    // 中央目录在数据源中的实际起始位置，以及ZIP64结束目录(或结束目录)的位置
+    pub cd_start: u64,
+    pub cd_end: u64,
 }
 
 // 中央目录结构
@@ -260,9 +524,65 @@ impl CentralDirectoryHeader {
         let compressed = self.get_compressed_size();
         let offset = self.get_local_header_offset();
 
-        uncompressed > MAX_ZIP_SIZE as u64
-            || compressed > MAX_ZIP_SIZE as u64
-            || offset > MAX_ZIP_SIZE as u64
+        uncompressed >= MAX_ZIP_SIZE as u64
+            || compressed >= MAX_ZIP_SIZE as u64
+            || offset >= MAX_ZIP_SIZE as u64
+    }
+
+    // 序列化为中央目录记录(包含签名)，需要时重新生成ZIP64额外字段
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let uncompressed = self.get_uncompressed_size();
+        let compressed = self.get_compressed_size();
+        let offset = self.get_local_header_offset();
+        let uncompressed_max = uncompressed >= MAX_ZIP_SIZE as u64;
+        let compressed_max = compressed >= MAX_ZIP_SIZE as u64;
+        let offset_max = offset >= MAX_ZIP_SIZE as u64;
+
+        // 旧的ZIP64字段可能已经过期，统一去掉后按需重建
+        let mut extra_field = Vec::new();
+        if uncompressed_max || compressed_max || offset_max {
+            let info = Zip64ExtendedInfo {
+                uncompressed_size: Some(uncompressed),
+                compressed_size: Some(compressed),
+                local_header_offset: Some(offset),
+                disk_start_number: None,
+            };
+            let zip64_data = info.to_bytes(uncompressed_max, compressed_max, offset_max);
+            extra_field.extend_from_slice(&ZIP64_EXTRA_FIELD_ID.to_le_bytes());
+            extra_field.extend_from_slice(&(zip64_data.len() as u16).to_le_bytes());
+            extra_field.extend_from_slice(&zip64_data);
+        }
+        extra_field.extend_from_slice(&strip_extra_field(&self.extra_field, ZIP64_EXTRA_FIELD_ID));
+
+        let clamp = |value: u64, is_max: bool| if is_max { MAX_ZIP_SIZE } else { value as u32 };
+
+        let mut data = Vec::with_capacity(
+            CENTRAL_DIR_HEADER_SIZE
+                + self.filename.len()
+                + extra_field.len()
+                + self.file_comment.len(),
+        );
+        data.extend_from_slice(&CENTRAL_DIR_HEADER_SIGNATURE.to_le_bytes());
+        data.extend_from_slice(&self.version_made.to_le_bytes());
+        data.extend_from_slice(&self.version_needed.to_le_bytes());
+        data.extend_from_slice(&self.flags.to_le_bytes());
+        data.extend_from_slice(&self.compression.to_le_bytes());
+        data.extend_from_slice(&self.mod_time.to_le_bytes());
+        data.extend_from_slice(&self.mod_date.to_le_bytes());
+        data.extend_from_slice(&self.crc32.to_le_bytes());
+        data.extend_from_slice(&clamp(compressed, compressed_max).to_le_bytes());
+        data.extend_from_slice(&clamp(uncompressed, uncompressed_max).to_le_bytes());
+        data.extend_from_slice(&(self.filename.len() as u16).to_le_bytes());
+        data.extend_from_slice(&(extra_field.len() as u16).to_le_bytes());
+        data.extend_from_slice(&(self.file_comment.len() as u16).to_le_bytes());
+        data.extend_from_slice(&self.disk_num.to_le_bytes());
+        data.extend_from_slice(&self.internal_attr.to_le_bytes());
+        data.extend_from_slice(&self.external_attr.to_le_bytes());
+        data.extend_from_slice(&clamp(offset, offset_max).to_le_bytes());
+        data.extend_from_slice(&self.filename);
+        data.extend_from_slice(&extra_field);
+        data.extend_from_slice(&self.file_comment);
+        data
     }
 
     pub fn get_uncompressed_size(&self) -> u64 {
@@ -279,15 +599,70 @@ impl CentralDirectoryHeader {
             .unwrap_or(self.compressed_size as u64)
     }
 
+    // 修改压缩后大小(如加密/解密后)，同时更新ZIP64扩展信息
+    pub fn set_compressed_size(&mut self, size: u64) {
+        self.compressed_size = size.min(MAX_ZIP_SIZE as u64) as u32;
+        if let Some(info) = self.zip64_extended_info.as_mut() {
+            info.compressed_size = Some(size);
+        } else if size >= MAX_ZIP_SIZE as u64 {
+            self.zip64_extended_info = Some(Zip64ExtendedInfo {
+                uncompressed_size: Some(self.uncompressed_size as u64),
+                compressed_size: Some(size),
+                local_header_offset: Some(self.local_header_offset as u64),
+                disk_start_number: None,
+            });
+        }
+    }
+
+    // 修改本地文件头偏移，同时更新ZIP64扩展信息
+    pub fn set_local_header_offset(&mut self, offset: u64) {
+        self.local_header_offset = offset.min(MAX_ZIP_SIZE as u64) as u32;
+        if let Some(info) = self.zip64_extended_info.as_mut() {
+            info.local_header_offset = Some(offset);
+        } else if offset >= MAX_ZIP_SIZE as u64 {
+            self.zip64_extended_info = Some(Zip64ExtendedInfo {
+                uncompressed_size: Some(self.uncompressed_size as u64),
+                compressed_size: Some(self.compressed_size as u64),
+                local_header_offset: Some(offset),
+                disk_start_number: None,
+            });
+        }
+    }
+
     pub fn get_local_header_offset(&self) -> u64 {
         self.zip64_extended_info
             .as_ref()
             .and_then(|info| info.local_header_offset)
             .unwrap_or(self.local_header_offset as u64)
     }
+
+    // ZipWriter::raw_write_file 写入该条目占用的字节数：本地文件头、数据、数据描述符和中央目录记录
+    // 假设本地文件头偏移小于4GB，中央目录记录中不需要ZIP64偏移字段
+    pub fn raw_copy_size(&self) -> u64 {
+        let compressed = self.get_compressed_size();
+        let uncompressed_max = self.get_uncompressed_size() >= MAX_ZIP_SIZE as u64;
+        let compressed_max = compressed >= MAX_ZIP_SIZE as u64;
+        let zip64 = uncompressed_max || compressed_max;
+        let name_len = self.filename.len() as u64;
+        let extra_len = strip_extra_field(&self.extra_field, ZIP64_EXTRA_FIELD_ID).len() as u64;
+
+        let mut local = LOCAL_FILE_HEADER_SIZE as u64 + name_len + extra_len;
+        let mut central =
+            CENTRAL_DIR_HEADER_SIZE as u64 + name_len + extra_len + self.file_comment.len() as u64;
+        if zip64 {
+            local += 4 + 16;
+            central += 4 + 8 * (uncompressed_max as u64 + compressed_max as u64);
+        }
+        let descriptor = match (self.flags & DATA_DESCRIPTOR_FLAG != 0, zip64) {
+            (false, _) => 0,
+            (true, false) => 16,
+            (true, true) => 24,
+        };
+        local + compressed + descriptor + central
+    }
 }
 
-struct CurrentFile<W: Write + Seek + 'static> {
+struct CurrentFile<W: ZipSink> {
     name: String,
     header_start: u64,
     data_start: u64,
@@ -305,7 +680,8 @@ struct CurrentFile<W: Write + Seek + 'static> {
     extra_field: Vec<u8>,
 
     skip_compression: bool, // 是否跳过压缩,跳过后，下面的三个字段才有用
-    compress_size: u32,     // 压缩后的大小
+    #[allow(dead_code)]
+    compress_size: u32, // 压缩后的大小
     uncompress_size: u64,   // 原始大小
     crc32: u32,             // 原始的crc32
 
@@ -315,76 +691,1888 @@ struct CurrentFile<W: Write + Seek + 'static> {
     // 用于自动切换到Store模式的原始数据缓冲区
     original_data_buffer: Vec<u8>,
     original_compression: CompressionMethod, // 保存原始压缩方法
+
+    internal_attr: u16,                // 内部属性(位0表示文本文件)
+    line_ending: LineEndingConversion, // 换行符转换方式
+    pending_cr: bool,                  // CRLF->LF转换时上一个块以CR结尾
+    zip64: bool,                       // 本地文件头是否预留了ZIP64额外字段
+    verifier: u32,                     // 加密头校验值
+    encryption: EncryptionMethod,      // 设置密码时的加密方式
+    aes: Option<AesExtraField>,        // AES加密条目的0x9901额外字段
+    auto_store: bool,                  // 是否仍在缓存原始数据以便压缩无效时切换为Store模式
+}
+
+impl<W: ZipSink> CurrentFile<W> {
+    fn write_data(&mut self, buf: &[u8]) -> io::Result<()> {
+        let encoder = self
+            .encoder
+            .as_mut()
+            .ok_or_else(|| io::Error::other("encoder already finished"))?;
+
+        // 跳过压缩时写入的是已经压缩好的数据，原样输出
+        if self.skip_compression {
+            encoder.write_all(buf)?;
+            self.bytes_written += buf.len() as u64;
+            return Ok(());
+        }
+
+        // 与原生zip一致，根据第一块数据判断是否为文本文件，二进制文件不做换行符转换
+        if self.bytes_written == 0 && !self.pending_cr {
+            if is_text_data(buf) {
+                self.internal_attr |= 0x1;
+            } else {
+                self.line_ending = LineEndingConversion::None;
+            }
+        }
+
+        let data = match self.line_ending {
+            LineEndingConversion::None => std::borrow::Cow::Borrowed(buf),
+            LineEndingConversion::LfToCrlf => {
+                let mut out = Vec::with_capacity(buf.len() + buf.len() / 16);
+                for &byte in buf {
+                    if byte == b'\n' && !self.pending_cr {
+                        out.push(b'\r');
+                    }
+                    out.push(byte);
+                    self.pending_cr = byte == b'\r';
+                }
+                std::borrow::Cow::Owned(out)
+            }
+            LineEndingConversion::CrlfToLf => {
+                let mut out = Vec::with_capacity(buf.len());
+                for &byte in buf {
+                    if self.pending_cr && byte != b'\n' {
+                        out.push(b'\r');
+                    }
+                    self.pending_cr = byte == b'\r';
+                    if !self.pending_cr {
+                        out.push(byte);
+                    }
+                }
+                std::borrow::Cow::Owned(out)
+            }
+        };
+
+        self.write_converted(&data)
+    }
+
+    fn write_converted(&mut self, data: &[u8]) -> io::Result<()> {
+        self.hasher.update(data);
+        self.bytes_written += data.len() as u64;
+
+        if self.auto_store
+            && !self.compression_level_specified
+            && self.original_compression != CompressionMethod::Stored
+        {
+            if self.original_data_buffer.len() + data.len() > AUTO_STORE_BUFFER_LIMIT {
+                // 文件太大，放弃自动切换
+                self.original_data_buffer = Vec::new();
+                self.auto_store = false;
+            } else {
+                self.original_data_buffer.extend_from_slice(data);
+            }
+        }
+
+        if let Some(encoder) = self.encoder.as_mut() {
+            encoder.write_all(data)?;
+        }
+        Ok(())
+    }
+
+    // CRLF->LF转换时，文件末尾单独的CR需要原样输出
+    fn flush_pending(&mut self) -> io::Result<()> {
+        if self.pending_cr && matches!(self.line_ending, LineEndingConversion::CrlfToLf) {
+            self.pending_cr = false;
+            self.write_converted(b"\r")?;
+        }
+        Ok(())
+    }
+}
+
+// 判断数据是否为文本：不包含NUL字节且控制字符很少
+fn is_text_data(buf: &[u8]) -> bool {
+    let sample = &buf[..buf.len().min(4096)];
+    if sample.contains(&0) {
+        return false;
+    }
+    let control = sample
+        .iter()
+        .filter(|&&b| b < 0x20 && !matches!(b, b'\n' | b'\r' | b'\t' | 0x0C | 0x1A))
+        .count();
+    control * 10 <= sample.len()
+}
+
+// 根据条目特性计算解压所需的最低版本
+fn version_needed_for(
+    compression: CompressionMethod,
+    encrypted: bool,
+    is_dir: bool,
+    zip64: bool,
+) -> u16 {
+    let mut version = VERSION_NEEDED;
+    if matches!(
+        compression,
+        CompressionMethod::Deflated | CompressionMethod::Deflate64
+    ) || encrypted
+        || is_dir
+    {
+        version = version.max(20);
+    }
+    if zip64 {
+        version = version.max(VERSION_NEEDED_ZIP64);
+    }
+    if compression == CompressionMethod::Bzip2 {
+        version = version.max(46);
+    }
+    if compression == CompressionMethod::Aes {
+        version = version.max(51);
+    }
+    if matches!(
+        compression,
+        CompressionMethod::Lzma | CompressionMethod::Zstd | CompressionMethod::Xz
+    ) {
+        version = version.max(63);
+    }
+    version
+}
+
+// 本地文件头是否需要预留ZIP64字段：按最坏的压缩膨胀和加密头估算
+fn may_need_zip64(uncompress_size: u64) -> bool {
+    uncompress_size + uncompress_size / 64 + 0x10000 >= MAX_ZIP_SIZE as u64
+}
+
+// ZipWriter的输出。除读写位置之外，归档还需要输出说明能否回写已写出的数据，并提供：
+// 分卷输出时记录不跨分卷、数据变短(自动切换Store模式)后去掉残留数据、全部写完后收尾(例如分卷改名)
+pub trait ZipSink: Write + Seek {
+    // 只能顺序写入时返回true，此时所有条目都使用数据描述符
+    fn is_streaming(&self) -> bool {
+        false
+    }
+
+    // 即将写入长度为 len 且不能跨分卷的记录，返回记录开始的分卷号和分卷内偏移
+    fn reserve(&mut self, _len: u64) -> io::Result<(u16, u64)> {
+        Ok((0, self.stream_position()?))
+    }
+
+    // 丢弃当前位置之后的数据，不支持时返回false，由ZipWriter补零覆盖残留数据
+    fn truncate(&mut self) -> io::Result<bool> {
+        Ok(false)
+    }
+
+    // 归档写完后调用
+    fn finish_archive(&mut self) -> io::Result<()> {
+        self.flush()
+    }
+}
+
+impl ZipSink for File {
+    fn truncate(&mut self) -> io::Result<bool> {
+        let position = self.stream_position()?;
+        self.set_len(position)?;
+        Ok(true)
+    }
+}
+
+impl ZipSink for io::Cursor<Vec<u8>> {
+    fn truncate(&mut self) -> io::Result<bool> {
+        let position = self.position() as usize;
+        self.get_mut().truncate(position);
+        Ok(true)
+    }
+}
+
+impl ZipSink for io::Cursor<&mut Vec<u8>> {
+    fn truncate(&mut self) -> io::Result<bool> {
+        let position = self.position() as usize;
+        self.get_mut().truncate(position);
+        Ok(true)
+    }
+}
+
+impl ZipSink for io::Cursor<&mut [u8]> {}
+
+impl<S: ZipSink> ZipSink for io::BufWriter<S> {
+    fn is_streaming(&self) -> bool {
+        self.get_ref().is_streaming()
+    }
+
+    fn reserve(&mut self, len: u64) -> io::Result<(u16, u64)> {
+        self.flush()?;
+        self.get_mut().reserve(len)
+    }
+
+    fn truncate(&mut self) -> io::Result<bool> {
+        self.flush()?;
+        self.get_mut().truncate()
+    }
+
+    fn finish_archive(&mut self) -> io::Result<()> {
+        self.flush()?;
+        self.get_mut().finish_archive()
+    }
+}
+
+impl<S: ZipSink + ?Sized> ZipSink for &mut S {
+    fn is_streaming(&self) -> bool {
+        (**self).is_streaming()
+    }
+
+    fn reserve(&mut self, len: u64) -> io::Result<(u16, u64)> {
+        (**self).reserve(len)
+    }
+
+    fn truncate(&mut self) -> io::Result<bool> {
+        (**self).truncate()
+    }
+
+    fn finish_archive(&mut self) -> io::Result<()> {
+        (**self).finish_archive()
+    }
+}
+
+// 不可定位输出(标准输出、管道等)的适配器：只记录已写入的字节数，
+// Seek仅支持查询当前位置，使ZipWriter可以用同一套逻辑处理两种输出
+pub struct StreamWriter<W: Write> {
+    inner: W,
+    position: u64,
+}
+
+impl<W: Write> StreamWriter<W> {
+    pub fn new(inner: W) -> Self {
+        Self { inner, position: 0 }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+impl<W: Write> Write for StreamWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.position += n as u64;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+impl<W: Write> ZipSink for StreamWriter<W> {
+    fn is_streaming(&self) -> bool {
+        true
+    }
+}
+
+impl<W: Write> Seek for StreamWriter<W> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        match pos {
+            SeekFrom::Current(0) => Ok(self.position),
+            SeekFrom::Start(offset) if offset == self.position => Ok(offset),
+            _ => Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "output is not seekable",
+            )),
+        }
+    }
+}
+
+// 分卷归档的设置，对应 -s/--sp/--sb/--sv
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SplitConfig {
+    pub size: u64,
+    pub pause: bool,
+    pub bell: bool,
+    pub verbose: bool,
 }
 
-pub struct ZipWriter<'a> {
+// 分卷输出：依次写入 name.z01、name.z02 ...，结束时最后一个分卷改名为 name.zip
+// 与StreamWriter一样只支持查询位置，条目都使用数据描述符，不回写已经写出的数据
+pub struct SplitWriter {
     file: File,
+    path: PathBuf,
+    config: SplitConfig,
+    // 当前分卷号(从0开始)和分卷内已写入的字节数
+    disk: u16,
+    disk_offset: u64,
+    position: u64,
+}
+
+impl SplitWriter {
+    pub fn new(path: &Path, config: SplitConfig) -> io::Result<Self> {
+        if config.size <= SPLIT_SIGNATURE_SIZE {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("split size {} too small", config.size),
+            ));
+        }
+        let mut writer = Self {
+            file: File::create(volume_path(path, 0))?,
+            path: path.to_path_buf(),
+            config,
+            disk: 0,
+            disk_offset: 0,
+            position: 0,
+        };
+        // 第一个分卷以分卷签名开头
+        writer.write_all(&SPLIT_SIGNATURE.to_le_bytes())?;
+        Ok(writer)
+    }
+
+    fn next_volume(&mut self) -> io::Result<()> {
+        self.file.flush()?;
+        if self.disk == u16::MAX - 1 {
+            return Err(io::Error::other("too many split volumes"));
+        }
+        if self.config.verbose {
+            LogConfig::println(&format!(
+                "Closing split {} ({} bytes)",
+                volume_path(&self.path, self.disk).display(),
+                self.disk_offset
+            ));
+        }
+        self.disk += 1;
+        let next = volume_path(&self.path, self.disk);
+        if self.config.pause {
+            // 写入可移动介质时等待用户更换磁盘
+            if self.config.bell {
+                eprint!("\x07");
+            }
+            eprint!(
+                "Insert disk for {} and hit ENTER (quit with ^C): ",
+                next.display()
+            );
+            let mut line = String::new();
+            io::stdin().read_line(&mut line)?;
+        }
+        if self.config.verbose {
+            LogConfig::println(&format!("Opening split {}", next.display()));
+        }
+        self.file = File::create(next)?;
+        self.disk_offset = 0;
+        Ok(())
+    }
+
+    // 分卷总数
+    pub fn volumes(&self) -> u16 {
+        self.disk + 1
+    }
+}
+
+// 第disk个(从0开始)分卷的路径，最后一个分卷在结束时改名为 path 本身
+pub fn volume_path(path: &Path, disk: u16) -> PathBuf {
+    path.with_extension(format!("z{:02}", disk as u32 + 1))
+}
+
+impl Write for SplitWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        if self.disk_offset >= self.config.size {
+            self.next_volume()?;
+        }
+        let room = (self.config.size - self.disk_offset).min(buf.len() as u64) as usize;
+        let n = self.file.write(&buf[..room])?;
+        self.disk_offset += n as u64;
+        self.position += n as u64;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+impl ZipSink for SplitWriter {
+    fn is_streaming(&self) -> bool {
+        true
+    }
+
+    // 当前分卷放不下时切换到下一个分卷
+    fn reserve(&mut self, len: u64) -> io::Result<(u16, u64)> {
+        if self.disk_offset + len.max(1) > self.config.size {
+            if len > self.config.size {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!(
+                        "record of {} bytes does not fit in split size {}",
+                        len, self.config.size
+                    ),
+                ));
+            }
+            self.next_volume()?;
+        }
+        Ok((self.disk, self.disk_offset))
+    }
+
+    // 完成写入：只有一个分卷时改用单段标记，最后一个分卷改名为 .zip
+    fn finish_archive(&mut self) -> io::Result<()> {
+        if self.disk == 0 {
+            self.file.seek(SeekFrom::Start(0))?;
+            self.file
+                .write_all(&SINGLE_SEGMENT_SIGNATURE.to_le_bytes())?;
+        }
+        self.file.flush()?;
+        std::fs::rename(volume_path(&self.path, self.disk), &self.path)
+    }
+}
+
+impl Seek for SplitWriter {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        match pos {
+            SeekFrom::Current(0) => Ok(self.position),
+            SeekFrom::Start(offset) if offset == self.position => Ok(offset),
+            _ => Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "split output is not seekable",
+            )),
+        }
+    }
+}
+
+// 从 from 开始查找签名，返回其位置
+fn find_signature<R: Read + Seek>(
+    file: &mut R,
+    from: u64,
+    signature: u32,
+) -> io::Result<Option<u64>> {
+    const CHUNK_SIZE: usize = 64 * 1024;
+    let signature = signature.to_le_bytes();
+    let mut buffer = vec![0u8; CHUNK_SIZE];
+    let mut pos = from;
+    loop {
+        file.seek(SeekFrom::Start(pos))?;
+        let mut len = 0;
+        while len < CHUNK_SIZE {
+            match file.read(&mut buffer[len..])? {
+                0 => break,
+                n => len += n,
+            }
+        }
+        if let Some(index) = buffer[..len].windows(4).position(|w| w == signature) {
+            return Ok(Some(pos + index as u64));
+        }
+        if len < CHUNK_SIZE {
+            return Ok(None);
+        }
+        // 相邻两块之间保留3个字节，避免签名被分在两块中
+        pos += (len - 3) as u64;
+    }
+}
+
+pub struct ZipWriter<W: ZipSink = File> {
+    // 写入条目数据时输出由当前条目的编码器持有，结束条目后归还
+    file: Option<W>,
     cd_headers: Vec<CentralDirectoryHeader>,
-    current_file: Option<CurrentFile<File>>,
+    current_file: Option<CurrentFile<W>>,
     output_path: String,
     archive_info: ArchiveFileInfo,
+    // 流式模式：输出不可定位，所有条目都使用数据描述符
+    streaming: bool,
+    // 曾经写到的最远位置，自动切换Store模式后数据可能变短
+    high_water: u64,
+}
+
+impl ZipWriter<File> {
+    pub fn new(path: &str) -> anyhow::Result<Self> {
+        let mut writer = Self::from_writer(File::create(path)?);
+        writer.output_path = path.to_string();
+        Ok(writer)
+    }
+
+    // 在归档前写入前缀数据(例如自解压程序)，条目偏移从文件开头计算
+    pub fn with_prefix(path: &str, prefix: &[u8]) -> anyhow::Result<Self> {
+        let mut file = File::create(path)?;
+        file.write_all(prefix)?;
+        let mut writer = Self::from_writer(file);
+        writer.output_path = path.to_string();
+        Ok(writer)
+    }
+}
+
+impl ZipWriter<SplitWriter> {
+    // 写入分卷归档，path 为最后一个分卷(.zip)的路径
+    pub fn new_split(path: &str, config: SplitConfig) -> anyhow::Result<Self> {
+        let mut writer = Self::from_writer(SplitWriter::new(Path::new(path), config)?);
+        writer.output_path = path.to_string();
+        Ok(writer)
+    }
+}
+
+impl<W: Write> ZipWriter<StreamWriter<W>> {
+    // 写入标准输出或管道等不可定位的输出
+    pub fn new_stream(writer: W) -> Self {
+        ZipWriter::from_writer(StreamWriter::new(writer))
+    }
+}
+
+impl<W: ZipSink> ZipWriter<W> {
+    // 写入任意实现了ZipSink的输出，例如内存中的Cursor<Vec<u8>>或者BufWriter<File>
+    pub fn from_writer(writer: W) -> Self {
+        Self {
+            streaming: writer.is_streaming(),
+            file: Some(writer),
+            cd_headers: Vec::new(),
+            current_file: None,
+            output_path: String::new(),
+            archive_info: ArchiveFileInfo::default(),
+            high_water: 0,
+        }
+    }
+
+    fn sink(&mut self) -> io::Result<&mut W> {
+        self.file
+            .as_mut()
+            .ok_or_else(|| io::Error::other("output is held by an unfinished entry"))
+    }
+
+    fn take_sink(&mut self) -> io::Result<W> {
+        self.file
+            .take()
+            .ok_or_else(|| io::Error::other("output is held by an unfinished entry"))
+    }
+
+    pub fn is_streaming(&self) -> bool {
+        self.streaming
+    }
+
+    pub fn output_path(&self) -> &str {
+        &self.output_path
+    }
+
+    // 设置归档注释，超出64KiB的部分会被截断
+    pub fn set_comment(&mut self, comment: &str) {
+        self.archive_info.comment = comment.to_string();
+    }
+
+    // 已经写入的条目
+    pub fn entries(&self) -> &[CentralDirectoryHeader] {
+        &self.cd_headers
+    }
+
+    // 设置已写入条目的注释(-c)，在 finish 之前调用
+    pub fn set_entry_comment(&mut self, index: usize, comment: &str) {
+        if let Some(header) = self.cd_headers.get_mut(index) {
+            let mut comment = comment.as_bytes().to_vec();
+            comment.truncate(MAX_COMMENT_SIZE);
+            header.file_comment = comment;
+        }
+    }
+
+    // 开始写入一个新条目，名称以'/'结尾时作为目录处理
+    pub fn start_file(&mut self, name: &str, options: FileOptions) -> anyhow::Result<()> {
+        if self.current_file.is_some() {
+            self.finish_file()?;
+        }
+
+        let is_dir = name.ends_with('/');
+        let skip_compression = options.skip_compression;
+        let compression = if is_dir {
+            CompressionMethod::Stored
+        } else {
+            options.compression_method
+        };
+        let (mod_time, mod_date) = options
+            .modification_time
+            .unwrap_or_else(|| datetime_to_dos(&Local::now()));
+
+        let mut options = options;
+        let line_ending = if skip_compression || is_dir {
+            LineEndingConversion::None
+        } else {
+            options.get_line_ending_conversion(true)
+        };
+
+        // 加密头需要CRC32的高字节；预先不知道CRC时改用数据描述符，并以修改时间校验密码
+        // 流式输出无法回填本地文件头，所有条目都使用数据描述符
+        let password = options
+            .password
+            .clone()
+            .filter(|password| !password.is_empty() && !is_dir && !skip_compression);
+        let crc_known = options.crc32 != 0 && matches!(line_ending, LineEndingConversion::None);
+        // AES不依赖CRC校验密码，只有ZipCrypto需要提前知道CRC
+        let aes = match (&password, options.encryption) {
+            (Some(_), EncryptionMethod::Aes(strength, version)) => Some(AesExtraField {
+                version,
+                strength,
+                compression,
+            }),
+            _ => None,
+        };
+        let header_method = if aes.is_some() {
+            CompressionMethod::Aes
+        } else {
+            compression
+        };
+        let mut flags = 0u16;
+        if compression == CompressionMethod::Lzma {
+            flags |= LZMA_EOS_FLAG;
+        }
+        let mut verifier = options.crc32;
+        if self.streaming || (password.is_some() && aes.is_none() && !crc_known) {
+            flags |= DATA_DESCRIPTOR_FLAG;
+        }
+        if password.is_some() {
+            flags |= ZIP_CRYPTO_FLAG;
+            if flags & DATA_DESCRIPTOR_FLAG != 0 {
+                verifier = (mod_time as u32) << 16;
+            }
+        }
+
+        let zip64 = options.large_file || may_need_zip64(options.uncompress_size);
+        let extra_field = if options.no_extra_field {
+            Vec::new()
+        } else {
+            strip_extra_field(
+                &strip_extra_field(&options.extra_field, ZIP64_EXTRA_FIELD_ID),
+                AES_EXTRA_FIELD_ID,
+            )
+        };
+
+        // 额外字段顺序固定为 ZIP64、AES、其他，结束时按此位置回填
+        let mut local_extra = Vec::new();
+        if zip64 {
+            local_extra.extend_from_slice(&ZIP64_EXTRA_FIELD_ID.to_le_bytes());
+            local_extra.extend_from_slice(&16u16.to_le_bytes());
+            local_extra.extend_from_slice(&[0u8; 16]);
+        }
+        if let Some(aes) = &aes {
+            local_extra.extend_from_slice(&aes.to_bytes());
+        }
+        local_extra.extend_from_slice(&extra_field);
+
+        let size_placeholder = if zip64 { MAX_ZIP_SIZE } else { 0 };
+        let mut header =
+            Vec::with_capacity(LOCAL_FILE_HEADER_SIZE + name.len() + local_extra.len());
+        header.extend_from_slice(&LOCAL_FILE_HEADER_SIGNATURE.to_le_bytes());
+        header.extend_from_slice(
+            &version_needed_for(header_method, password.is_some(), is_dir, zip64).to_le_bytes(),
+        );
+        header.extend_from_slice(&flags.to_le_bytes());
+        header.extend_from_slice(&header_method.to_le_bytes());
+        header.extend_from_slice(&mod_time.to_le_bytes());
+        header.extend_from_slice(&mod_date.to_le_bytes());
+        header.extend_from_slice(&0u32.to_le_bytes()); // CRC32，结束时回填
+        header.extend_from_slice(&size_placeholder.to_le_bytes()); // 压缩后大小
+        header.extend_from_slice(&size_placeholder.to_le_bytes()); // 原始大小
+        header.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        header.extend_from_slice(&(local_extra.len() as u16).to_le_bytes());
+        header.extend_from_slice(name.as_bytes());
+        header.extend_from_slice(&local_extra);
+        // 分卷输出时 header_start 为分卷内偏移，此时不会回写本地文件头
+        let (disk_num, header_start) = self.sink()?.reserve(header.len() as u64)?;
+        let sink = self.sink()?;
+        sink.write_all(&header)?;
+        let data_start = sink.stream_position()?;
+
+        // 跳过压缩时写入的是已压缩的数据，编码器只需原样输出
+        let encoder = CompressionEncoder::new(
+            self.take_sink()?,
+            if skip_compression {
+                CompressionMethod::Stored
+            } else {
+                compression
+            },
+            options.compression_level,
+            password.as_deref(),
+            options.encryption,
+            verifier,
+        )?;
+
+        log::debug!(
+            "start_file: {} method={} level={} flags={:#x} zip64={}",
+            name,
+            compression,
+            options.compression_level,
+            flags,
+            zip64
+        );
+
+        self.current_file = Some(CurrentFile {
+            name: name.to_string(),
+            header_start,
+            data_start,
+            compression,
+            flags,
+            password,
+            hasher: Hasher::new(),
+            bytes_written: 0,
+            encoder: Some(encoder),
+            mod_time,
+            mod_date,
+            external_attr: options.external_attr,
+            disk_num,
+            extra_field,
+            skip_compression,
+            compress_size: options.compress_size,
+            uncompress_size: options.uncompress_size,
+            crc32: options.crc32,
+            compression_level_specified: options.compression_level_specified,
+            original_data_buffer: Vec::new(),
+            original_compression: compression,
+            internal_attr: 0,
+            line_ending,
+            pending_cr: false,
+            zip64,
+            verifier,
+            encryption: options.encryption,
+            aes,
+            auto_store: !skip_compression && !self.streaming,
+        });
+
+        Ok(())
+    }
+
+    // 添加目录条目
+    pub fn add_directory(
+        &mut self,
+        name: &str,
+        options: FileOptions,
+    ) -> anyhow::Result<CentralDirectoryHeader> {
+        let name = if name.ends_with('/') {
+            name.to_string()
+        } else {
+            format!("{}/", name)
+        };
+        self.start_file(&name, options)?;
+        self.finish_file()
+    }
+
+    // 从文件系统添加一个条目(普通文件、目录或符号链接)
+    // 调用前应先通过 FileOptions::set_file_path 设置时间、属性和CRC
+    pub fn add_file_from_path(
+        &mut self,
+        name: &str,
+        path: &Path,
+        mut options: FileOptions,
+    ) -> anyhow::Result<CentralDirectoryHeader> {
+        use std::os::unix::ffi::OsStrExt;
+        use std::os::unix::fs::PermissionsExt;
+
+        let link_metadata = std::fs::symlink_metadata(path)?;
+        if options.store_symlinks && link_metadata.file_type().is_symlink() {
+            // 符号链接以链接目标作为内容存储
+            let target = std::fs::read_link(path)?;
+            let target = target.as_os_str().as_bytes();
+            let mut hasher = Hasher::new();
+            hasher.update(target);
+            options.with_compression(CompressionMethod::Stored);
+            options.external_attr = ((link_metadata.permissions().mode() & 0xFFFF) << 16) | 0x20;
+            options.uncompress_size = target.len() as u64;
+            options.crc32 = hasher.finalize();
+            options.convert_lf_to_crlf = false;
+            options.convert_crlf_to_lf = false;
+            self.start_file(name, options)?;
+            self.write_all(target)?;
+            return self.finish_file();
+        }
+
+        if path.is_dir() {
+            return self.add_directory(name, options);
+        }
+
+        let mut file = File::open(path)?;
+        self.start_file(name, options)?;
+        io::copy(&mut file, self)?;
+        self.finish_file()
+    }
+
+    // 原样复制另一个归档中的条目(不解压也不解密)，保留压缩方法、标志和额外字段
+    pub fn raw_copy_file<R: Read + Seek + Send + 'static>(
+        &mut self,
+        file: &ZipFile<R>,
+    ) -> anyhow::Result<CentralDirectoryHeader> {
+        let mut reader = file.raw_reader();
+        self.raw_write_file(file.header().clone(), |sink| io::copy(&mut reader, sink))
+    }
+
+    // 按给定的中央目录信息写入已经处理好的条目数据(例如重新加密后的数据)，不经过压缩编码器
+    // write_data 写出的字节数必须与 header 中的压缩后大小一致
+    pub fn raw_write_file<F>(
+        &mut self,
+        mut header: CentralDirectoryHeader,
+        write_data: F,
+    ) -> anyhow::Result<CentralDirectoryHeader>
+    where
+        F: FnOnce(&mut dyn Write) -> io::Result<u64>,
+    {
+        if self.current_file.is_some() {
+            self.finish_file()?;
+        }
+
+        let compressed_size = header.get_compressed_size();
+        let uncompressed_size = header.get_uncompressed_size();
+        let zip64 =
+            compressed_size >= MAX_ZIP_SIZE as u64 || uncompressed_size >= MAX_ZIP_SIZE as u64;
+        let descriptor = header.flags & DATA_DESCRIPTOR_FLAG != 0;
+        let extra_field = strip_extra_field(&header.extra_field, ZIP64_EXTRA_FIELD_ID);
+
+        let mut local_extra = Vec::new();
+        if zip64 {
+            local_extra.extend_from_slice(&ZIP64_EXTRA_FIELD_ID.to_le_bytes());
+            local_extra.extend_from_slice(&16u16.to_le_bytes());
+            local_extra.extend_from_slice(&uncompressed_size.to_le_bytes());
+            local_extra.extend_from_slice(&compressed_size.to_le_bytes());
+        }
+        local_extra.extend_from_slice(&extra_field);
+
+        // 使用数据描述符的条目，本地文件头中的CRC和大小置零
+        let (crc32, sizes) = match (descriptor, zip64) {
+            (true, false) => (0, [0, 0]),
+            (true, true) => (0, [MAX_ZIP_SIZE, MAX_ZIP_SIZE]),
+            (false, true) => (header.crc32, [MAX_ZIP_SIZE, MAX_ZIP_SIZE]),
+            (false, false) => (
+                header.crc32,
+                [compressed_size as u32, uncompressed_size as u32],
+            ),
+        };
+        if zip64 {
+            header.version_needed = header.version_needed.max(VERSION_NEEDED_ZIP64);
+        }
+
+        let mut local =
+            Vec::with_capacity(LOCAL_FILE_HEADER_SIZE + header.filename.len() + local_extra.len());
+        local.extend_from_slice(&LOCAL_FILE_HEADER_SIGNATURE.to_le_bytes());
+        local.extend_from_slice(&header.version_needed.to_le_bytes());
+        local.extend_from_slice(&header.flags.to_le_bytes());
+        local.extend_from_slice(&header.compression.to_le_bytes());
+        local.extend_from_slice(&header.mod_time.to_le_bytes());
+        local.extend_from_slice(&header.mod_date.to_le_bytes());
+        local.extend_from_slice(&crc32.to_le_bytes());
+        local.extend_from_slice(&sizes[0].to_le_bytes());
+        local.extend_from_slice(&sizes[1].to_le_bytes());
+        local.extend_from_slice(&(header.filename.len() as u16).to_le_bytes());
+        local.extend_from_slice(&(local_extra.len() as u16).to_le_bytes());
+        local.extend_from_slice(&header.filename);
+        local.extend_from_slice(&local_extra);
+        let (disk_num, header_start) = self.sink()?.reserve(local.len() as u64)?;
+        let sink = self.sink()?;
+        sink.write_all(&local)?;
+
+        let copied = write_data(sink)?;
+        if copied != compressed_size {
+            return Err(ZipError::InvalidArchive(format!(
+                "{}: expected {} bytes of entry data, copied {}",
+                String::from_utf8_lossy(&header.filename),
+                compressed_size,
+                copied
+            ))
+            .into());
+        }
+
+        if descriptor {
+            let mut data = Vec::with_capacity(24);
+            data.extend_from_slice(&DATA_DESCRIPTOR_SIGNATURE.to_le_bytes());
+            data.extend_from_slice(&header.crc32.to_le_bytes());
+            if zip64 {
+                data.extend_from_slice(&compressed_size.to_le_bytes());
+                data.extend_from_slice(&uncompressed_size.to_le_bytes());
+            } else {
+                data.extend_from_slice(&(compressed_size as u32).to_le_bytes());
+                data.extend_from_slice(&(uncompressed_size as u32).to_le_bytes());
+            }
+            sink.write_all(&data)?;
+        }
+
+        let zip64_needed = zip64 || header_start >= MAX_ZIP_SIZE as u64;
+        if zip64_needed {
+            header.version_needed = header.version_needed.max(VERSION_NEEDED_ZIP64);
+        }
+        let clamp = |value: u64| value.min(MAX_ZIP_SIZE as u64) as u32;
+        header.compressed_size = clamp(compressed_size);
+        header.uncompressed_size = clamp(uncompressed_size);
+        header.local_header_offset = clamp(header_start);
+        header.disk_num = disk_num;
+        header.extra_field = extra_field;
+        header.zip64_extended_info = zip64_needed.then_some(Zip64ExtendedInfo {
+            uncompressed_size: Some(uncompressed_size),
+            compressed_size: Some(compressed_size),
+            local_header_offset: Some(header_start),
+            disk_start_number: None,
+        });
+
+        self.cd_headers.push(header.clone());
+        Ok(header)
+    }
+
+    // 结束当前条目：回填CRC和大小(或写入数据描述符)，并记录中央目录信息
+    pub fn finish_file(&mut self) -> anyhow::Result<CentralDirectoryHeader> {
+        let mut current = self
+            .current_file
+            .take()
+            .ok_or_else(|| anyhow::anyhow!("No file in progress"))?;
+        current.flush_pending()?;
+        let mut crypt_overhead = 0;
+        if let Some(encoder) = current.encoder.take() {
+            crypt_overhead = encoder.crypt_overhead();
+            self.file = Some(encoder.finish()?);
+        }
+
+        let mut data_end = self.sink()?.stream_position()?;
+        let (crc32, uncompressed_size) = if current.skip_compression {
+            (current.crc32, current.uncompress_size)
+        } else {
+            (current.hasher.clone().finalize(), current.bytes_written)
+        };
+        let encrypted = current.flags & ZIP_CRYPTO_FLAG != 0;
+        let mut compressed_size = data_end - current.data_start;
+
+        // 压缩后没有变小时改用Store模式重写数据(与原生zip行为一致)
+        if current.auto_store
+            && !current.compression_level_specified
+            && current.original_compression != CompressionMethod::Stored
+            && compressed_size - crypt_overhead >= uncompressed_size
+            && current.original_data_buffer.len() as u64 == uncompressed_size
+        {
+            log::debug!(
+                "{}: compressed {} >= original {}, switching to stored",
+                current.name,
+                compressed_size - crypt_overhead,
+                uncompressed_size
+            );
+            self.high_water = self.high_water.max(data_end);
+            self.sink()?.seek(SeekFrom::Start(current.data_start))?;
+            let mut encoder = CompressionEncoder::new(
+                self.take_sink()?,
+                CompressionMethod::Stored,
+                0,
+                current.password.as_deref(),
+                current.encryption,
+                current.verifier,
+            )?;
+            encoder.write_all(&current.original_data_buffer)?;
+            self.file = Some(encoder.finish()?);
+
+            let sink = self.sink()?;
+            data_end = sink.stream_position()?;
+            compressed_size = data_end - current.data_start;
+            current.compression = CompressionMethod::Stored;
+
+            // 同时清除LZMA结束标记标志
+            current.flags &= !LZMA_EOS_FLAG;
+            sink.seek(SeekFrom::Start(current.header_start + 6))?;
+            sink.write_all(&current.flags.to_le_bytes())?;
+            match current.aes.as_mut() {
+                Some(aes) => {
+                    // AES条目的实际压缩方法位于0x9901额外字段的最后两个字节
+                    aes.compression = CompressionMethod::Stored;
+                    let zip64_len = if current.zip64 { 20 } else { 0 };
+                    let method_pos = current.header_start
+                        + LOCAL_FILE_HEADER_SIZE as u64
+                        + current.name.len() as u64
+                        + zip64_len
+                        + AesExtraField::SIZE as u64
+                        - 2;
+                    sink.seek(SeekFrom::Start(method_pos))?;
+                    sink.write_all(&current.compression.to_le_bytes())?;
+                }
+                None => sink.write_all(&current.compression.to_le_bytes())?,
+            }
+        }
+
+        // AE-2不记录CRC32，数据完整性由认证码保证
+        let crc32 = match current.aes {
+            Some(aes) if aes.version == AesVendorVersion::Ae2 => 0,
+            _ => crc32,
+        };
+
+        let too_large =
+            compressed_size >= MAX_ZIP_SIZE as u64 || uncompressed_size >= MAX_ZIP_SIZE as u64;
+        if too_large && !current.zip64 {
+            return Err(ZipError::UnsupportedFeature(format!(
+                "{} grew beyond 4 GiB without a reserved ZIP64 field",
+                current.name
+            ))
+            .into());
+        }
+
+        let sink = self.sink()?;
+        if current.flags & DATA_DESCRIPTOR_FLAG != 0 {
+            // 数据描述符紧跟在数据之后
+            sink.seek(SeekFrom::Start(data_end))?;
+            let mut descriptor = Vec::with_capacity(24);
+            descriptor.extend_from_slice(&DATA_DESCRIPTOR_SIGNATURE.to_le_bytes());
+            descriptor.extend_from_slice(&crc32.to_le_bytes());
+            if current.zip64 {
+                descriptor.extend_from_slice(&compressed_size.to_le_bytes());
+                descriptor.extend_from_slice(&uncompressed_size.to_le_bytes());
+            } else {
+                descriptor.extend_from_slice(&(compressed_size as u32).to_le_bytes());
+                descriptor.extend_from_slice(&(uncompressed_size as u32).to_le_bytes());
+            }
+            sink.write_all(&descriptor)?;
+        } else {
+            // 回填本地文件头中的CRC和大小
+            sink.seek(SeekFrom::Start(current.header_start + 14))?;
+            let mut fields = Vec::with_capacity(12);
+            fields.extend_from_slice(&crc32.to_le_bytes());
+            if current.zip64 {
+                fields.extend_from_slice(&MAX_ZIP_SIZE.to_le_bytes());
+                fields.extend_from_slice(&MAX_ZIP_SIZE.to_le_bytes());
+            } else {
+                fields.extend_from_slice(&(compressed_size as u32).to_le_bytes());
+                fields.extend_from_slice(&(uncompressed_size as u32).to_le_bytes());
+            }
+            sink.write_all(&fields)?;
+
+            if current.zip64 {
+                let zip64_data_pos = current.header_start
+                    + LOCAL_FILE_HEADER_SIZE as u64
+                    + current.name.len() as u64
+                    + 4;
+                sink.seek(SeekFrom::Start(zip64_data_pos))?;
+                sink.write_all(&uncompressed_size.to_le_bytes())?;
+                sink.write_all(&compressed_size.to_le_bytes())?;
+            }
+            sink.seek(SeekFrom::Start(data_end))?;
+        }
+
+        let local_header_offset = current.header_start;
+        let is_dir = current.name.ends_with('/');
+        let (header_method, extra_field) = match current.aes {
+            Some(aes) => {
+                let mut extra_field = aes.to_bytes().to_vec();
+                extra_field.extend_from_slice(&current.extra_field);
+                (CompressionMethod::Aes, extra_field)
+            }
+            None => (current.compression, current.extra_field),
+        };
+        let zip64_needed = too_large || local_header_offset >= MAX_ZIP_SIZE as u64;
+        let clamp = |value: u64| value.min(MAX_ZIP_SIZE as u64) as u32;
+        let header = CentralDirectoryHeader {
+            version_made: VERSION_MADE,
+            version_needed: version_needed_for(header_method, encrypted, is_dir, zip64_needed),
+            flags: current.flags,
+            compression: header_method,
+            mod_time: current.mod_time,
+            mod_date: current.mod_date,
+            crc32,
+            compressed_size: clamp(compressed_size),
+            uncompressed_size: clamp(uncompressed_size),
+            filename: current.name.into_bytes(),
+            extra_field,
+            file_comment: Vec::new(),
+            disk_num: current.disk_num,
+            internal_attr: current.internal_attr,
+            external_attr: current.external_attr,
+            local_header_offset: clamp(local_header_offset),
+            zip64_extended_info: zip64_needed.then_some(Zip64ExtendedInfo {
+                uncompressed_size: Some(uncompressed_size),
+                compressed_size: Some(compressed_size),
+                local_header_offset: Some(local_header_offset),
+                disk_start_number: None,
+            }),
+        };
+
+        self.cd_headers.push(header.clone());
+        Ok(header)
+    }
+
+    // 写入中央目录和结束目录记录，完成归档并返回输出
+    pub fn finish(mut self) -> anyhow::Result<W> {
+        if self.current_file.is_some() {
+            self.finish_file()?;
+        }
+
+        let central_dir: Vec<Vec<u8>> = self.cd_headers.iter().map(|h| h.to_bytes()).collect();
+        let cd_size: u64 = central_dir.iter().map(|record| record.len() as u64).sum();
+        let mut comment = self.archive_info.comment.as_bytes().to_vec();
+        comment.truncate(MAX_COMMENT_SIZE);
+
+        // 自动切换Store模式让数据变短时，先截断输出去掉残留数据；
+        // 输出无法截断时在中央目录前补零使归档结尾覆盖旧数据(读取时按偏移定位中央目录，不受影响)
+        let mut sink = self.take_sink()?;
+        let position = sink.stream_position()?;
+        let tail_len = cd_size + (END_OF_CENTRAL_DIR_SIZE + comment.len()) as u64;
+        let truncated = sink.truncate()?;
+        if !truncated && position + tail_len < self.high_water {
+            let padding = self.high_water - position - tail_len;
+            io::copy(&mut io::repeat(0).take(padding), &mut sink)?;
+        }
+
+        // 分卷输出时中央目录记录可以跨分卷，记录每条记录开始的分卷以统计最后一个分卷上的条目数
+        let (cd_disk, cd_start) = sink.reserve(0)?;
+        let mut record_disks = Vec::with_capacity(central_dir.len());
+        for record in &central_dir {
+            record_disks.push(sink.reserve(0)?.0);
+            sink.write_all(record)?;
+        }
+        let total_entries = self.cd_headers.len() as u64;
+
+        let zip64 = total_entries >= MAX_ZIP_ENTRIES as u64
+            || cd_size >= MAX_ZIP_SIZE as u64
+            || cd_start >= MAX_ZIP_SIZE as u64;
+        // ZIP64结束目录、定位器和结束目录记录必须位于最后一个分卷
+        let mut end_len = (END_OF_CENTRAL_DIR_SIZE + comment.len()) as u64;
+        if zip64 {
+            end_len +=
+                (ZIP64_END_OF_CENTRAL_DIR_SIZE + ZIP64_END_OF_CENTRAL_DIR_LOCATOR_SIZE) as u64;
+        }
+        let (last_disk, end_start) = sink.reserve(end_len)?;
+        let entries_on_disk = record_disks
+            .iter()
+            .filter(|&&disk| disk == last_disk)
+            .count() as u64;
+        if zip64 {
+            let zip64_end = Zip64EndOfCentralDir {
+                disk_number: last_disk as u32,
+                central_dir_disk: cd_disk as u32,
+                entries_on_disk,
+                total_entries,
+                central_dir_size: cd_size,
+                central_dir_offset: cd_start,
+                ..Default::default()
+            };
+            sink.write_all(&zip64_end.to_bytes())?;
+
+            // ZIP64结束目录定位器
+            let mut locator = Vec::with_capacity(ZIP64_END_OF_CENTRAL_DIR_LOCATOR_SIZE);
+            locator.extend_from_slice(&ZIP64_END_OF_CENTRAL_DIR_LOCATOR_SIGNATURE.to_le_bytes());
+            locator.extend_from_slice(&(last_disk as u32).to_le_bytes()); // ZIP64结束目录所在磁盘
+            locator.extend_from_slice(&end_start.to_le_bytes());
+            locator.extend_from_slice(&(last_disk as u32 + 1).to_le_bytes()); // 磁盘总数
+            sink.write_all(&locator)?;
+        }
+
+        let clamp_entries = |count: u64| count.min(MAX_ZIP_ENTRIES as u64) as u16;
+        let mut record = Vec::with_capacity(END_OF_CENTRAL_DIR_SIZE + comment.len());
+        record.extend_from_slice(&END_OF_CENTRAL_DIR_SIGNATURE.to_le_bytes());
+        record.extend_from_slice(&last_disk.to_le_bytes()); // 当前磁盘号
+        record.extend_from_slice(&cd_disk.to_le_bytes()); // 中央目录开始的磁盘号
+        record.extend_from_slice(&clamp_entries(entries_on_disk).to_le_bytes());
+        record.extend_from_slice(&clamp_entries(total_entries).to_le_bytes());
+        record.extend_from_slice(&(cd_size.min(MAX_ZIP_SIZE as u64) as u32).to_le_bytes());
+        record.extend_from_slice(&(cd_start.min(MAX_ZIP_SIZE as u64) as u32).to_le_bytes());
+        record.extend_from_slice(&(comment.len() as u16).to_le_bytes());
+        record.extend_from_slice(&comment);
+        sink.write_all(&record)?;
+        sink.finish_archive()?;
+
+        log::debug!(
+            "Finished {}: {} entries, central directory at {} ({} bytes)",
+            self.output_path,
+            total_entries,
+            cd_start,
+            cd_size
+        );
+        Ok(sink)
+    }
+}
+
+impl<W: ZipSink> Write for ZipWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let current = self
+            .current_file
+            .as_mut()
+            .ok_or_else(|| io::Error::other("No file has been started"))?;
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        current.write_data(buf)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self.current_file.as_mut().and_then(|f| f.encoder.as_mut()) {
+            Some(encoder) => encoder.flush(),
+            None => self.sink()?.flush(),
+        }
+    }
+}
+
+// 读取不可信归档时的资源限制，None表示不限制
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ReadLimits {
+    // 所有条目解压后的总大小上限
+    pub max_total_uncompressed: Option<u64>,
+    // 单个条目的最大压缩比(解压大小/压缩大小)
+    pub max_compression_ratio: Option<u64>,
+    // 条目数量上限
+    pub max_entries: Option<u64>,
+    // 允许本地文件头范围相互重叠的条目数，重叠是"quoted overlap"类压缩炸弹的特征
+    pub max_overlapping_entries: Option<usize>,
+}
+
+impl ReadLimits {
+    fn check_entry_count(&self, count: u64) -> Result<(), ZipError> {
+        match self.max_entries {
+            Some(max) if count > max => Err(ZipError::LimitExceeded(format!(
+                "{} entries, limit is {}",
+                count, max
+            ))),
+            _ => Ok(()),
+        }
+    }
+
+    fn check_headers(&self, headers: &[CentralDirectoryHeader]) -> Result<(), ZipError> {
+        let name =
+            |header: &CentralDirectoryHeader| String::from_utf8_lossy(&header.filename).to_string();
+
+        if let Some(max_ratio) = self.max_compression_ratio {
+            for header in headers {
+                let uncompressed = header.get_uncompressed_size();
+                let compressed = header.get_compressed_size();
+                if uncompressed > compressed.saturating_mul(max_ratio) {
+                    return Err(ZipError::LimitExceeded(format!(
+                        "{}: compression ratio {}/{} above {}",
+                        name(header),
+                        uncompressed,
+                        compressed,
+                        max_ratio
+                    )));
+                }
+            }
+        }
+
+        if let Some(max_total) = self.max_total_uncompressed {
+            let total = headers
+                .iter()
+                .fold(0u64, |sum, h| sum.saturating_add(h.get_uncompressed_size()));
+            if total > max_total {
+                return Err(ZipError::LimitExceeded(format!(
+                    "total uncompressed size {} above {}",
+                    total, max_total
+                )));
+            }
+        }
+
+        if let Some(max_overlaps) = self.max_overlapping_entries {
+            // 按本地文件头偏移排序，区间下界为本地头固定部分+文件名+压缩数据
+            let mut ranges: Vec<(u64, u64, &CentralDirectoryHeader)> = headers
+                .iter()
+                .map(|h| {
+                    let start = h.get_local_header_offset();
+                    let len = (LOCAL_FILE_HEADER_SIZE + h.filename.len()) as u64
+                        + h.get_compressed_size();
+                    (start, start.saturating_add(len), h)
+                })
+                .collect();
+            ranges.sort_by_key(|&(start, _, _)| start);
+
+            let mut overlaps = 0usize;
+            let mut furthest: Option<(u64, &CentralDirectoryHeader)> = None;
+            for &(start, end, header) in &ranges {
+                if let Some((prev_end, prev)) = furthest {
+                    if start < prev_end {
+                        overlaps += 1;
+                        if overlaps > max_overlaps {
+                            return Err(ZipError::OverlappingEntries(name(header), name(prev)));
+                        }
+                    }
+                }
+                if furthest.map_or(true, |(prev_end, _)| end > prev_end) {
+                    furthest = Some((end, header));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+// 归档数据源在ZipArchive与其全部ZipFile之间共享，每次读取前先定位，
+// 因此多个条目可以交替或在多个线程中同时读取
+type SharedReader<R> = Arc<Mutex<R>>;
+
+// 在共享数据源的指定位置读满缓冲区
+fn read_exact_at<R: Read + Seek>(shared: &Mutex<R>, buf: &mut [u8], offset: u64) -> io::Result<()> {
+    let mut reader = shared
+        .lock()
+        .map_err(|_| io::Error::new(io::ErrorKind::Other, "archive reader lock poisoned"))?;
+    reader.seek(SeekFrom::Start(offset))?;
+    reader.read_exact(buf)
+}
+
+// 分卷归档的读取端：把 name.z01 ... name.zip 按顺序拼接成一个逻辑流，单个文件视为只有一个分卷
+#[derive(Debug)]
+pub struct SplitReader {
+    volumes: Vec<File>,
+    // 每个分卷在逻辑流中的起始位置，最后一项为总长度
+    starts: Vec<u64>,
+    position: u64,
+}
+
+impl SplitReader {
+    // last 为最后一个分卷(即归档本身)，之前的分卷按 volume_path 命名
+    pub fn open(path: &Path, last: File, last_disk: u16) -> Result<Self, ZipError> {
+        let mut volumes = Vec::with_capacity(last_disk as usize + 1);
+        for disk in 0..last_disk {
+            let volume = volume_path(path, disk);
+            let file = File::open(&volume)
+                .map_err(|_| ZipError::MissingVolume(volume.display().to_string()))?;
+            volumes.push(file);
+        }
+        volumes.push(last);
+
+        let mut starts = vec![0];
+        for volume in &volumes {
+            let len = volume.metadata()?.len();
+            starts.push(starts[starts.len() - 1] + len);
+        }
+        Ok(Self {
+            volumes,
+            starts,
+            position: 0,
+        })
+    }
+
+    // 每个分卷在逻辑流中的起始位置
+    pub fn volume_starts(&self) -> &[u64] {
+        &self.starts[..self.volumes.len()]
+    }
+}
+
+impl Read for SplitReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        // 找到当前位置所在的分卷，每次最多读到该分卷末尾
+        let disk = self.starts.partition_point(|&start| start <= self.position);
+        if disk == 0 || disk > self.volumes.len() || buf.is_empty() {
+            return Ok(0);
+        }
+        let disk = disk - 1;
+        let room = (self.starts[disk + 1] - self.position).min(buf.len() as u64) as usize;
+        let volume = &mut self.volumes[disk];
+        volume.seek(SeekFrom::Start(self.position - self.starts[disk]))?;
+        let n = volume.read(&mut buf[..room])?;
+        self.position += n as u64;
+        Ok(n)
+    }
+}
+
+impl Seek for SplitReader {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let total = self.starts[self.volumes.len()];
+        let position = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::End(delta) => total.checked_add_signed(delta),
+            SeekFrom::Current(delta) => self.position.checked_add_signed(delta),
+        };
+        self.position = position.ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "invalid seek to a negative position",
+            )
+        })?;
+        Ok(self.position)
+    }
+}
+
+#[derive(Debug)]
+pub struct ZipArchive<R = SplitReader> {
+    reader: SharedReader<R>,
+    cd_headers: Vec<CentralDirectoryHeader>,
+    arhive_info: ArchiveFileInfo,
+    // 分卷归档的全部分卷路径，单个文件时为None
+    split_files: Option<Vec<String>>,
+}
+
+impl ZipArchive<SplitReader> {
+    pub fn new(path: &str) -> anyhow::Result<Self> {
+        Self::with_limits(path, ReadLimits::default())
+    }
+
+    // 打开归档并在解析中央目录时检查资源限制
+    // 结束目录记录中的磁盘号不为0时，同时打开同名的 .z01 ... 分卷
+    pub fn with_limits(path: &str, limits: ReadLimits) -> anyhow::Result<Self> {
+        let path = Path::new(path);
+        let mut file = File::open(path)?;
+        let last_disk = ZipArchive::<File>::last_disk(&mut file)?;
+        let reader = SplitReader::open(path, file, last_disk)?;
+        let volume_starts = reader.volume_starts().to_vec();
+        let mut archive = Self::from_volumes(reader, &volume_starts, limits)?;
+        if last_disk > 0 {
+            log::debug!(
+                "{}: split archive with {} volumes",
+                path.display(),
+                last_disk + 1
+            );
+            let mut files: Vec<String> = (0..last_disk)
+                .map(|disk| volume_path(path, disk).display().to_string())
+                .collect();
+            files.push(path.display().to_string());
+            archive.split_files = Some(files);
+        }
+        Ok(archive)
+    }
+
+    // 分卷归档的全部分卷路径
+    pub fn split_files(&self) -> Option<&[String]> {
+        self.split_files.as_deref()
+    }
+
+    // -FF：中央目录损坏或缺失时，扫描本地文件头重建条目列表
+    pub fn scan(path: &str) -> anyhow::Result<Self> {
+        let path = Path::new(path);
+        let reader = SplitReader::open(path, File::open(path)?, 0)?;
+        Self::scan_local_headers(reader)
+    }
+}
+
+impl<R: Read + Seek> ZipArchive<R> {
+    // 从任意可定位的数据源读取归档，例如内存中的Cursor<Vec<u8>>
+    pub fn from_reader(reader: R) -> anyhow::Result<Self> {
+        Self::from_reader_with_limits(reader, ReadLimits::default())
+    }
+
+    pub fn from_reader_with_limits(reader: R, limits: ReadLimits) -> anyhow::Result<Self> {
+        Self::from_volumes(reader, &[0], limits)
+    }
+
+    // 按本地文件头重建条目，跳过无法确定数据范围的条目
+    pub fn scan_local_headers(mut reader: R) -> anyhow::Result<Self> {
+        let file_len = reader.seek(SeekFrom::End(0))?;
+        let mut cd_headers = Vec::new();
+        let mut pos = 0;
+        while let Some(start) = find_signature(&mut reader, pos, LOCAL_FILE_HEADER_SIGNATURE)? {
+            match Self::read_local_entry(&mut reader, start, file_len)? {
+                Some((header, next)) => {
+                    cd_headers.push(header);
+                    pos = next;
+                }
+                None => {
+                    log::debug!("skipping damaged local header at {}", start);
+                    pos = start + 4;
+                }
+            }
+        }
+        Ok(ZipArchive {
+            reader: Arc::new(Mutex::new(reader)),
+            cd_headers,
+            arhive_info: ArchiveFileInfo::default(),
+            split_files: None,
+        })
+    }
+
+    // 由 start 处的本地文件头构造中央目录记录，返回记录和条目之后的位置
+    // 头部或数据不完整时返回None；本地文件头中没有的文件属性按普通文件或目录补全
+    fn read_local_entry(
+        file: &mut R,
+        start: u64,
+        file_len: u64,
+    ) -> anyhow::Result<Option<(CentralDirectoryHeader, u64)>> {
+        if start + LOCAL_FILE_HEADER_SIZE as u64 > file_len {
+            return Ok(None);
+        }
+        let mut local = [0u8; LOCAL_FILE_HEADER_SIZE];
+        file.seek(SeekFrom::Start(start))?;
+        file.read_exact(&mut local)?;
+        let read_u16 = |pos: usize| u16::from_le_bytes([local[pos], local[pos + 1]]);
+        let read_u32 = |pos: usize| u32::from_le_bytes(local[pos..pos + 4].try_into().unwrap());
+
+        let name_len = read_u16(26) as usize;
+        let extra_len = read_u16(28) as usize;
+        let data_start = start + (LOCAL_FILE_HEADER_SIZE + name_len + extra_len) as u64;
+        if name_len == 0 || data_start > file_len {
+            return Ok(None);
+        }
+        let mut name_extra = vec![0u8; name_len + extra_len];
+        file.read_exact(&mut name_extra)?;
+        let (filename, extra) = name_extra.split_at(name_len);
+
+        let flags = read_u16(6);
+        let zip64 = find_extra_field(extra, ZIP64_EXTRA_FIELD_ID).unwrap_or(&[]);
+        let size = |pos: usize, index: usize| match read_u32(pos) {
+            MAX_ZIP_SIZE => zip64
+                .get(index * 8..index * 8 + 8)
+                .map(|bytes| u64::from_le_bytes(bytes.try_into().unwrap())),
+            size => Some(size as u64),
+        };
+        let (crc32, compressed_size, uncompressed_size, next) = if flags & DATA_DESCRIPTOR_FLAG != 0
+        {
+            match Self::find_data_descriptor(file, data_start, file_len)? {
+                Some(descriptor) => descriptor,
+                None => return Ok(None),
+            }
+        } else {
+            match (size(18, 1), size(22, 0)) {
+                (Some(compressed), Some(uncompressed)) => (
+                    read_u32(14),
+                    compressed,
+                    uncompressed,
+                    data_start + compressed,
+                ),
+                _ => return Ok(None),
+            }
+        };
+        if data_start + compressed_size > file_len {
+            return Ok(None);
+        }
+
+        let mode = if filename.ends_with(b"/") {
+            0o40755
+        } else {
+            0o100644
+        };
+        let clamp = |value: u64| value.min(MAX_ZIP_SIZE as u64) as u32;
+        let mut header = CentralDirectoryHeader {
+            version_made: VERSION_MADE,
+            version_needed: read_u16(4),
+            flags,
+            compression: CompressionMethod::from(read_u16(8)),
+            mod_time: read_u16(10),
+            mod_date: read_u16(12),
+            crc32,
+            compressed_size: clamp(compressed_size),
+            uncompressed_size: clamp(uncompressed_size),
+            external_attr: mode << 16,
+            filename: filename.to_vec(),
+            extra_field: strip_extra_field(extra, ZIP64_EXTRA_FIELD_ID),
+            ..Default::default()
+        };
+        if compressed_size >= MAX_ZIP_SIZE as u64 || uncompressed_size >= MAX_ZIP_SIZE as u64 {
+            header.zip64_extended_info = Some(Zip64ExtendedInfo {
+                uncompressed_size: Some(uncompressed_size),
+                compressed_size: Some(compressed_size),
+                local_header_offset: None,
+                disk_start_number: None,
+            });
+        }
+        header.set_local_header_offset(start);
+        Ok(Some((header, next)))
+    }
+
+    // 查找数据之后的数据描述符：描述符中的压缩大小必须与它到数据开始的距离一致
+    // 返回CRC、压缩后大小、原始大小和描述符之后的位置
+    fn find_data_descriptor(
+        file: &mut R,
+        data_start: u64,
+        file_len: u64,
+    ) -> io::Result<Option<(u32, u64, u64, u64)>> {
+        let mut from = data_start;
+        while let Some(pos) = find_signature(file, from, DATA_DESCRIPTOR_SIGNATURE)? {
+            let compressed = pos - data_start;
+            let mut record = vec![0u8; (file_len - pos - 4).min(20) as usize];
+            file.seek(SeekFrom::Start(pos + 4))?;
+            file.read_exact(&mut record)?;
+            let read_u32 = |at: usize| u32::from_le_bytes(record[at..at + 4].try_into().unwrap());
+            let read_u64 = |at: usize| u64::from_le_bytes(record[at..at + 8].try_into().unwrap());
+            if record.len() >= 12 && read_u32(4) as u64 == compressed {
+                return Ok(Some((
+                    read_u32(0),
+                    compressed,
+                    read_u32(8) as u64,
+                    pos + 16,
+                )));
+            }
+            if record.len() >= 20 && read_u64(4) == compressed {
+                return Ok(Some((read_u32(0), compressed, read_u64(12), pos + 24)));
+            }
+            from = pos + 1;
+        }
+        Ok(None)
+    }
+
+    // volume_starts 为每个分卷在数据源中的起始位置，用于把分卷内的偏移换算为数据源中的偏移
+    fn from_volumes(
+        mut reader: R,
+        volume_starts: &[u64],
+        limits: ReadLimits,
+    ) -> anyhow::Result<Self> {
+        let (arhive_info, cd_headers) =
+            Self::read_central_directory(&mut reader, volume_starts, &limits)?;
+        Ok(ZipArchive {
+            reader: Arc::new(Mutex::new(reader)),
+            cd_headers,
+            arhive_info,
+            split_files: None,
+        })
+    }
+
+    // 结束目录记录所在的磁盘号，即分卷归档的最后一个分卷号
+    fn last_disk(file: &mut R) -> anyhow::Result<u16> {
+        let end_record_pos = Self::find_end_of_central_dir(file)?;
+        file.seek(SeekFrom::Start(end_record_pos + 4))?;
+        let mut disk = [0u8; 2];
+        file.read_exact(&mut disk)?;
+        let disk = u16::from_le_bytes(disk);
+        if disk != MAX_ZIP_ENTRIES || !Self::has_zip64_locator(file, end_record_pos)? {
+            return Ok(disk);
+        }
+        // 磁盘号溢出时以ZIP64定位器中的磁盘总数为准
+        file.seek(SeekFrom::Start(
+            end_record_pos - ZIP64_END_OF_CENTRAL_DIR_LOCATOR_SIZE as u64 + 16,
+        ))?;
+        let mut total = [0u8; 4];
+        file.read_exact(&mut total)?;
+        let last = u32::from_le_bytes(total).saturating_sub(1);
+        u16::try_from(last).map_err(|_| {
+            ZipError::UnsupportedFeature(format!("split archive with {} volumes", last + 1)).into()
+        })
+    }
+
+    #[allow(dead_code)]
+    pub fn archive_info(&self) -> &ArchiveFileInfo {
+        &self.arhive_info
+    }
+
+    #[allow(dead_code)]
+    pub fn get_total_size(&self) -> u64 {
+        let mut reader = self.reader.lock().unwrap();
+        reader.seek(SeekFrom::End(0)).unwrap_or(0)
+    }
+
+    pub fn get_total_original_size(&self) -> u64 {
+        self.cd_headers
+            .iter()
+            .map(|header| header.get_uncompressed_size())
+            .sum()
+    }
+    pub fn get_total_compressed_size(&self) -> u64 {
+        self.cd_headers
+            .iter()
+            .map(|header| header.get_compressed_size())
+            .sum()
+    }
+
+    // 从文件末尾向前查找结束目录记录，返回其起始位置
+    // 归档注释最长64KiB，因此最多只需要搜索 22 + 65535 字节
+    fn find_end_of_central_dir(file: &mut R) -> anyhow::Result<u64> {
+        let file_len = file.seek(SeekFrom::End(0))?;
+        if file_len < END_OF_CENTRAL_DIR_SIZE as u64 {
+            return Err(ZipError::InvalidArchive("file too small".to_string()).into());
+        }
+
+        let search_len = file_len.min((END_OF_CENTRAL_DIR_SIZE + MAX_COMMENT_SIZE) as u64);
+        let search_start = file_len - search_len;
+        file.seek(SeekFrom::Start(search_start))?;
+        let mut buffer = vec![0u8; search_len as usize];
+        file.read_exact(&mut buffer)?;
+
+        let signature = END_OF_CENTRAL_DIR_SIGNATURE.to_le_bytes();
+        let mut candidate = None;
+        for pos in (0..=buffer.len() - END_OF_CENTRAL_DIR_SIZE).rev() {
+            if buffer[pos..pos + 4] != signature {
+                continue;
+            }
+            // 注释恰好结束于文件末尾的记录优先，避免把注释中的签名误认为结束目录；
+            // 没有这样的记录时才接受归档后面带有多余数据的情况
+            let comment_len = u16::from_le_bytes([buffer[pos + 20], buffer[pos + 21]]) as usize;
+            let end = pos + END_OF_CENTRAL_DIR_SIZE + comment_len;
+            if end == buffer.len() {
+                return Ok(search_start + pos as u64);
+            }
+            if end < buffer.len() && candidate.is_none() {
+                candidate = Some(search_start + pos as u64);
+            }
+        }
+
+        candidate.ok_or_else(|| {
+            ZipError::InvalidArchive("end of central directory record not found".to_string()).into()
+        })
+    }
+
+    // 检查结束目录记录之前是否紧跟ZIP64结束目录定位器
+    fn has_zip64_locator(file: &mut R, end_record_pos: u64) -> anyhow::Result<bool> {
+        if end_record_pos < ZIP64_END_OF_CENTRAL_DIR_LOCATOR_SIZE as u64 {
+            return Ok(false);
+        }
+        file.seek(SeekFrom::Start(
+            end_record_pos - ZIP64_END_OF_CENTRAL_DIR_LOCATOR_SIZE as u64,
+        ))?;
+        let mut signature = [0u8; 4];
+        file.read_exact(&mut signature)?;
+        Ok(signature == ZIP64_END_OF_CENTRAL_DIR_LOCATOR_SIGNATURE.to_le_bytes())
+    }
+
+    // 读取结束目录记录(必要时包括ZIP64结束目录)以及全部中央目录记录
+    fn read_central_directory(
+        file: &mut R,
+        volume_starts: &[u64],
+        limits: &ReadLimits,
+    ) -> anyhow::Result<(ArchiveFileInfo, Vec<CentralDirectoryHeader>)> {
+        // 分卷内的偏移换算为数据源中的偏移
+        let locate = |disk: u32, offset: u64| {
+            volume_starts
+                .get(disk as usize)
+                .map_or(offset, |start| start + offset)
+        };
+        let end_record_pos = Self::find_end_of_central_dir(file)?;
+        file.seek(SeekFrom::Start(end_record_pos))?;
+        let mut record = [0u8; END_OF_CENTRAL_DIR_SIZE];
+        file.read_exact(&mut record)?;
+
+        let mut cd_disk = u16::from_le_bytes([record[6], record[7]]) as u32;
+        let num_entries = u16::from_le_bytes([record[10], record[11]]);
+        let size = u32::from_le_bytes([record[12], record[13], record[14], record[15]]);
+        let offset = u32::from_le_bytes([record[16], record[17], record[18], record[19]]);
+        let comment_len = u16::from_le_bytes([record[20], record[21]]) as usize;
+        let mut comment = vec![0u8; comment_len];
+        file.read_exact(&mut comment)?;
+
+        let mut archive_info = ArchiveFileInfo {
+            num_entries,
+            size,
+            offset,
+            comment: String::from_utf8_lossy(&comment).to_string(),
+            ..Default::default()
+        };
+
+        // 中央目录应当紧接在ZIP64结束目录或结束目录之前
+        let mut cd_end = end_record_pos;
+        if Self::has_zip64_locator(file, end_record_pos)? {
+            let zip64 = Self::read_zip64_info(file, end_record_pos, volume_starts)?;
+            log::debug!("ZIP64 end of central directory: {:?}", zip64);
+            cd_end = end_record_pos.saturating_sub(
+                ZIP64_END_OF_CENTRAL_DIR_LOCATOR_SIZE as u64 + 12 + zip64.size_of_record,
+            );
+            cd_disk = zip64.central_dir_disk;
+            archive_info.is_zip64 = true;
+            archive_info.zip64_num_entries = Some(zip64.total_entries);
+            archive_info.zip64_size = Some(zip64.central_dir_size);
+            archive_info.zip64_offset = Some(zip64.central_dir_offset);
+        }
+
+        let total_entries = archive_info
+            .zip64_num_entries
+            .unwrap_or(archive_info.num_entries as u64);
+        let cd_size = archive_info.zip64_size.unwrap_or(archive_info.size as u64);
+        let mut cd_offset = locate(
+            cd_disk,
+            archive_info
+                .zip64_offset
+                .unwrap_or(archive_info.offset as u64),
+        );
+        limits.check_entry_count(total_entries)?;
+
+        // 与原生unzip一致，单个文件的归档前面有多余数据时，按中央目录的实际位置修正所有偏移
+        if volume_starts.len() <= 1 {
+            if let Some(shift) = cd_end
+                .checked_sub(cd_size)
+                .and_then(|start| start.checked_sub(cd_offset))
+                .filter(|&shift| shift > 0)
+            {
+                file.seek(SeekFrom::Start(cd_offset + shift))?;
+                let mut signature = [0u8; 4];
+                file.read_exact(&mut signature)?;
+                if signature == CENTRAL_DIR_HEADER_SIGNATURE.to_le_bytes() {
+                    log::debug!("{} extra bytes at beginning of archive", shift);
+                    archive_info.offset_shift = shift;
+                    cd_offset += shift;
+                }
+            }
+        }
+        archive_info.cd_start = cd_offset;
+        archive_info.cd_end = cd_end;
+
+        if cd_offset
+            .checked_add(cd_size)
+            .map_or(true, |end| end > end_record_pos)
+        {
+            return Err(ZipError::InvalidArchive(format!(
+                "central directory (offset {}, size {}) beyond end record at {}",
+                cd_offset, cd_size, end_record_pos
+            ))
+            .into());
+        }
+
+        file.seek(SeekFrom::Start(cd_offset))?;
+        let mut buffer = vec![0u8; cd_size as usize];
+        file.read_exact(&mut buffer)?;
+
+        let mut cd_headers = Vec::new();
+        let mut pos = 0usize;
+        while (cd_headers.len() as u64) < total_entries {
+            let (header, consumed) = Self::parse_central_directory_header(&buffer[pos..])?;
+            cd_headers.push(header);
+            pos += consumed;
+        }
+        if volume_starts.len() > 1 {
+            for header in &mut cd_headers {
+                let disk = header
+                    .zip64_extended_info
+                    .as_ref()
+                    .and_then(|info| info.disk_start_number)
+                    .unwrap_or(header.disk_num as u32);
+                header.set_local_header_offset(locate(disk, header.get_local_header_offset()));
+            }
+        }
+        if archive_info.offset_shift > 0 {
+            for header in &mut cd_headers {
+                let offset = header.get_local_header_offset() + archive_info.offset_shift;
+                header.set_local_header_offset(offset);
+            }
+        }
+        limits.check_headers(&cd_headers)?;
+
+        log::debug!(
+            "Read {} central directory entries (offset {}, size {})",
+            cd_headers.len(),
+            cd_offset,
+            cd_size
+        );
+
+        Ok((archive_info, cd_headers))
+    }
+
+    // 解析一条中央目录记录，返回记录以及其占用的字节数
+    fn parse_central_directory_header(
+        data: &[u8],
+    ) -> anyhow::Result<(CentralDirectoryHeader, usize)> {
+        if data.len() < CENTRAL_DIR_HEADER_SIZE
+            || data[0..4] != CENTRAL_DIR_HEADER_SIGNATURE.to_le_bytes()
+        {
+            return Err(ZipError::InvalidArchive(
+                "central directory header signature not found".into(),
+            )
+            .into());
+        }
 
-    // 新增分卷支持
-    split_size: Option<u64>,  // 分卷大小
-    current_split_index: u16, // 当前分卷索引
-    base_name: String,        // 基础文件名
-    // 回调函数，用于在每个分卷完成后调用
-    split_callback: Option<Box<dyn FnMut(u16) -> anyhow::Result<PathBuf> + 'a>>,
-    split_bell: bool,    // 是否响铃
-    split_verbose: bool, // 是否显示分卷的详细输出
-}
+        let read_u16 = |at: usize| u16::from_le_bytes([data[at], data[at + 1]]);
+        let read_u32 =
+            |at: usize| u32::from_le_bytes([data[at], data[at + 1], data[at + 2], data[at + 3]]);
 
-#[derive(Debug)]
-pub struct ZipArchive {
-    file: File,
-    cd_headers: Vec<CentralDirectoryHeader>,
-    arhive_info: ArchiveFileInfo,
-    // 分割文件支持
-    #[allow(dead_code)]
-    split_files: Option<Vec<String>>, // 分割文件路径列表
-    #[allow(dead_code)]
-    base_name: Option<String>, // 基础文件名
-}
+        let filename_len = read_u16(28) as usize;
+        let extra_len = read_u16(30) as usize;
+        let comment_len = read_u16(32) as usize;
+        let total_len = CENTRAL_DIR_HEADER_SIZE + filename_len + extra_len + comment_len;
+        if data.len() < total_len {
+            return Err(
+                ZipError::InvalidArchive("central directory header truncated".into()).into(),
+            );
+        }
 
-impl ZipArchive {
-    pub fn new(path: &str) -> anyhow::Result<Self> {
-        let file = File::open(path)?;
-        Ok(ZipArchive {
-            file,
-            cd_headers: Vec::new(),
-            arhive_info: ArchiveFileInfo::default(),
-            split_files: None,
-            base_name: None,
-        })
-    }
+        let filename_end = CENTRAL_DIR_HEADER_SIZE + filename_len;
+        let extra_end = filename_end + extra_len;
 
-    #[allow(dead_code)]
-    pub fn archive_info(&self) -> &ArchiveFileInfo {
-        &self.arhive_info
-    }
+        let mut header = CentralDirectoryHeader {
+            version_made: read_u16(4),
+            version_needed: read_u16(6),
+            flags: read_u16(8),
+            compression: CompressionMethod::from(read_u16(10)),
+            mod_time: read_u16(12),
+            mod_date: read_u16(14),
+            crc32: read_u32(16),
+            compressed_size: read_u32(20),
+            uncompressed_size: read_u32(24),
+            disk_num: read_u16(34),
+            internal_attr: read_u16(36),
+            external_attr: read_u32(38),
+            local_header_offset: read_u32(42),
+            filename: data[CENTRAL_DIR_HEADER_SIZE..filename_end].to_vec(),
+            extra_field: data[filename_end..extra_end].to_vec(),
+            file_comment: data[extra_end..total_len].to_vec(),
+            zip64_extended_info: None,
+        };
 
-    #[allow(dead_code)]
-    pub fn get_total_size(&self) -> u64 {
-        self.file.metadata().unwrap().len()
-    }
+        if let Some(zip64_data) = find_extra_field(&header.extra_field, ZIP64_EXTRA_FIELD_ID) {
+            header.zip64_extended_info = Some(Zip64ExtendedInfo::from_extra_field(
+                zip64_data,
+                header.uncompressed_size == MAX_ZIP_SIZE,
+                header.compressed_size == MAX_ZIP_SIZE,
+                header.local_header_offset == MAX_ZIP_SIZE,
+                header.disk_num == MAX_ZIP_ENTRIES,
+            )?);
+        }
 
-    pub fn get_total_original_size(&self) -> u64 {
-        self.cd_headers
-            .iter()
-            .map(|header| header.uncompressed_size as u64)
-            .sum()
-    }
-    pub fn get_total_compressed_size(&self) -> u64 {
-        self.cd_headers
-            .iter()
-            .map(|header| header.compressed_size as u64)
-            .sum()
+        Ok((header, total_len))
     }
 
     // 读取ZIP64信息
     fn read_zip64_info(
-        file: &mut File,
+        file: &mut R,
         end_record_pos: u64,
+        volume_starts: &[u64],
     ) -> anyhow::Result<Zip64EndOfCentralDir> {
         // 检查ZIP64结束目录定位器
         if end_record_pos < ZIP64_END_OF_CENTRAL_DIR_LOCATOR_SIZE as u64 {
@@ -392,7 +2580,7 @@ impl ZipArchive {
         }
 
         let locator_pos = end_record_pos - ZIP64_END_OF_CENTRAL_DIR_LOCATOR_SIZE as u64;
-        file.seek(io::SeekFrom::Start(locator_pos))?;
+        file.seek(SeekFrom::Start(locator_pos))?;
 
         // 读取ZIP64结束目录定位器签名
         let mut signature = [0u8; 4];
@@ -405,7 +2593,7 @@ impl ZipArchive {
         let mut locator_data = [0u8; 16];
         file.read_exact(&mut locator_data)?;
 
-        let _central_dir_disk = u32::from_le_bytes([
+        let zip64_end_disk = u32::from_le_bytes([
             locator_data[0],
             locator_data[1],
             locator_data[2],
@@ -428,8 +2616,11 @@ impl ZipArchive {
             locator_data[15],
         ]);
 
-        // 读取ZIP64结束目录记录
-        file.seek(io::SeekFrom::Start(zip64_end_offset))?;
+        // 读取ZIP64结束目录记录，偏移相对于其所在的分卷
+        let zip64_end_offset = volume_starts
+            .get(zip64_end_disk as usize)
+            .map_or(zip64_end_offset, |start| start + zip64_end_offset);
+        file.seek(SeekFrom::Start(zip64_end_offset))?;
 
         // 读取ZIP64结束目录记录签名
         file.read_exact(&mut signature)?;
@@ -519,7 +2710,7 @@ impl ZipArchive {
         })
     }
 
-    pub fn by_index_raw(&self, index: usize) -> anyhow::Result<ZipFile> {
+    pub fn by_index_raw(&self, index: usize) -> anyhow::Result<ZipFile<R>> {
         if index >= self.cd_headers.len() {
             return Err(anyhow::anyhow!("索引超出范围"));
         }
@@ -527,30 +2718,152 @@ impl ZipArchive {
         self.get_zip_file(&self.cd_headers[index])
     }
 
+    // 中央目录中的全部条目
+    pub fn entries(&self) -> &[CentralDirectoryHeader] {
+        &self.cd_headers
+    }
+
     #[allow(clippy::len_without_is_empty)]
     pub fn len(&self) -> usize {
         self.cd_headers.len()
     }
 
     // 新增方法获取完整的ZipFile对象
-    fn get_zip_file(&self, header: &CentralDirectoryHeader) -> anyhow::Result<ZipFile> {
+    fn get_zip_file(&self, header: &CentralDirectoryHeader) -> anyhow::Result<ZipFile<R>> {
         // 使用ZIP64信息（如果可用）
         let local_header_offset = header.get_local_header_offset();
         let compressed_size = header.get_compressed_size();
 
-        // 读取整个条目数据(包括本地文件头和数据)
+        // 本地文件头中的额外字段不一定与中央目录一致，需要读取真实长度
         // 30 = 本地文件头固定部分大小(签名4 + 版本2 + 标志2 + 压缩方法2 + 时间2 + 日期2 + CRC4 + 压缩大小4 + 未压缩大小4 + 文件名长度2 + 额外字段长度2)
-        let local_header_size = 30 + header.filename.len() as u64 + header.extra_field.len() as u64;
-
-        let total_size = local_header_size + compressed_size;
+        let mut local_header = [0u8; LOCAL_FILE_HEADER_SIZE];
+        read_exact_at(&self.reader, &mut local_header, local_header_offset).map_err(|e| {
+            ZipError::InvalidArchive(format!(
+                "cannot read local header of {} at {}: {}",
+                String::from_utf8_lossy(&header.filename),
+                local_header_offset,
+                e
+            ))
+        })?;
+        if local_header[0..4] != LOCAL_FILE_HEADER_SIGNATURE.to_le_bytes() {
+            return Err(ZipError::InvalidArchive(format!(
+                "local header signature not found for {} at {}",
+                String::from_utf8_lossy(&header.filename),
+                local_header_offset
+            ))
+            .into());
+        }
+        let filename_len = u16::from_le_bytes([local_header[26], local_header[27]]) as u64;
+        let extra_len = u16::from_le_bytes([local_header[28], local_header[29]]) as u64;
+        let local_header_size = LOCAL_FILE_HEADER_SIZE as u64 + filename_len + extra_len;
 
+        let data_start = local_header_offset + local_header_size;
         Ok(ZipFile {
             header: header.clone(),
-            data_start: local_header_offset + local_header_size,
-            data_end: local_header_offset + total_size,
-            file: self.file.try_clone()?.into(),
+            data_start,
+            data_end: data_start + compressed_size,
+            reader: self.reader.clone(),
         })
     }
+
+    // 检查结束记录与中央目录是否一致(-T)：各条记录的大小之和应等于记录的中央目录大小
+    pub fn check_central_directory(&self) -> Result<(), ZipError> {
+        let info = &self.arhive_info;
+        let total = self.cd_headers.len() as u64;
+        if let Some(zip64_entries) = info.zip64_num_entries {
+            if zip64_entries != total {
+                return Err(ZipError::InvalidArchive(format!(
+                    "ZIP64 end record lists {} entries, central directory has {}",
+                    zip64_entries, total
+                )));
+            }
+        }
+        // 16位的条目数没有置为0xFFFF时，应当与ZIP64结束目录中的条目数一致
+        if info.num_entries != MAX_ZIP_ENTRIES && info.num_entries as u64 != total {
+            let source = if info.zip64_num_entries.is_some() {
+                "ZIP64 end record lists"
+            } else {
+                "central directory has"
+            };
+            return Err(ZipError::InvalidArchive(format!(
+                "end record lists {} entries, {} {}",
+                info.num_entries, source, total
+            )));
+        }
+        if info.offset_shift > 0 {
+            return Err(ZipError::InvalidArchive(format!(
+                "{} extra bytes at beginning or within zipfile",
+                info.offset_shift
+            )));
+        }
+        let size: u64 = self
+            .cd_headers
+            .iter()
+            .map(|header| {
+                (CENTRAL_DIR_HEADER_SIZE
+                    + header.filename.len()
+                    + header.extra_field.len()
+                    + header.file_comment.len()) as u64
+            })
+            .sum();
+        let expected = info.zip64_size.unwrap_or(info.size as u64);
+        if size != expected {
+            return Err(ZipError::InvalidArchive(format!(
+                "central directory is {} bytes, end record says {}",
+                size, expected
+            )));
+        }
+        // 中央目录与结束记录之间不应有其他数据
+        if info.cd_start + expected != info.cd_end {
+            return Err(ZipError::InvalidArchive(format!(
+                "central directory ends at {}, end record starts at {}",
+                info.cd_start + expected,
+                info.cd_end
+            )));
+        }
+        Ok(())
+    }
+
+    // 按名称查找条目
+    pub fn by_name(&self, name: &str) -> anyhow::Result<ZipFile<R>> {
+        let header = self
+            .cd_headers
+            .iter()
+            .find(|header| header.filename == name.as_bytes())
+            .ok_or_else(|| ZipError::EntryNotFound(name.to_string()))?;
+        self.get_zip_file(header)
+    }
+}
+
+// 在额外字段中查找指定标识的数据块，返回不含头部(标识+长度)的数据
+pub fn find_extra_field(extra: &[u8], id: u16) -> Option<&[u8]> {
+    let mut pos = 0;
+    while pos + 4 <= extra.len() {
+        let header_id = u16::from_le_bytes([extra[pos], extra[pos + 1]]);
+        let size = u16::from_le_bytes([extra[pos + 2], extra[pos + 3]]) as usize;
+        let data = extra.get(pos + 4..pos + 4 + size)?;
+        if header_id == id {
+            return Some(data);
+        }
+        pos += 4 + size;
+    }
+    None
+}
+
+// 去掉额外字段中指定标识的数据块
+pub fn strip_extra_field(extra: &[u8], id: u16) -> Vec<u8> {
+    let mut result = Vec::with_capacity(extra.len());
+    let mut pos = 0;
+    while pos + 4 <= extra.len() {
+        let header_id = u16::from_le_bytes([extra[pos], extra[pos + 1]]);
+        let size = u16::from_le_bytes([extra[pos + 2], extra[pos + 3]]) as usize;
+        let end = (pos + 4 + size).min(extra.len());
+        if header_id != id {
+            result.extend_from_slice(&extra[pos..end]);
+        }
+        pos = end;
+    }
+    result
 }
 
 // 新增枚举定义转换类型
@@ -586,6 +2899,10 @@ pub struct FileOptions {
 
     // 新增：标记压缩级别是否由外部显式指定
     pub compression_level_specified: bool, // 压缩级别是否由外部指定
+
+    pub large_file: bool, // 大小未知(如标准输入)，预留ZIP64字段
+
+    pub encryption: EncryptionMethod, // 设置密码时使用的加密方式
 }
 
 impl FileOptions {
@@ -690,6 +3007,10 @@ impl FileOptions {
         self.password = Some(password.to_string());
     }
 
+    pub fn with_encryption(&mut self, encryption: EncryptionMethod) {
+        self.encryption = encryption;
+    }
+
     #[allow(dead_code)]
     pub fn with_skip_compression(&mut self, skip: bool) -> &mut Self {
         self.skip_compression = skip;
@@ -707,6 +3028,10 @@ impl FileOptions {
                 self.compression_level = 6; // 默认使用优化的压缩级别
             } else if method == CompressionMethod::Bzip2 {
                 self.compression_level = 9; // Bzip2默认压缩级别
+            } else if method == CompressionMethod::Zstd {
+                self.compression_level = 6; // 对应zstd默认级别3
+            } else if matches!(method, CompressionMethod::Lzma | CompressionMethod::Xz) {
+                self.compression_level = 6; // liblzma默认预设
             }
         }
     }
@@ -756,8 +3081,7 @@ impl FileOptions {
 
         // 高16位: Unix属性 (文件类型+权限)
         // 低16位: DOS属性 (兼容Windows)
-        self.external_attr =
-            ((mode as u32 & 0xFFFF) << 16) | if metadata.is_dir() { 0x10 } else { 0x20 };
+        self.external_attr = ((mode & 0xFFFF) << 16) | if metadata.is_dir() { 0x10 } else { 0x20 };
 
         Ok(())
     }
@@ -771,10 +3095,10 @@ impl FileOptions {
             .as_secs() as u32;
 
         let mut field = Vec::with_capacity(7);
-        field.extend_from_slice(&0x5455u16.to_le_bytes()); // Header ID
+        field.extend_from_slice(&UT_EXTRA_FIELD_ID.to_le_bytes()); // Header ID
         field.extend_from_slice(&5u16.to_le_bytes()); // Data Size
         field.push(0x01); // Flags: modtime present
-        field.extend_from_slice(&(mod_time as u32).to_le_bytes()); // modtime (UTC, u32)
+        field.extend_from_slice(&mod_time.to_le_bytes()); // modtime (UTC, u32)
 
         self.extra_field = field.clone();
         Ok(())
@@ -782,16 +3106,129 @@ impl FileOptions {
 }
 
 // 新增 ZipFile 结构体
-#[derive(Debug, Clone)]
-pub struct ZipFile {
+pub struct ZipFile<R = SplitReader> {
     header: CentralDirectoryHeader,
-    #[allow(dead_code)]
     data_start: u64,
     data_end: u64,
-    file: Arc<File>,
+    reader: SharedReader<R>,
+}
+
+impl<R> Clone for ZipFile<R> {
+    fn clone(&self) -> Self {
+        ZipFile {
+            header: self.header.clone(),
+            data_start: self.data_start,
+            data_end: self.data_end,
+            reader: self.reader.clone(),
+        }
+    }
+}
+
+impl<R> std::fmt::Debug for ZipFile<R> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ZipFile")
+            .field("header", &self.header)
+            .field("data_start", &self.data_start)
+            .field("data_end", &self.data_end)
+            .finish_non_exhaustive()
+    }
+}
+
+// 读取条目原始(压缩/加密后)数据，自行记录位置因此多个条目可以同时读取
+pub struct ZipFileRawReader<R = SplitReader> {
+    reader: SharedReader<R>,
+    position: u64,
+    end: u64,
+}
+
+impl<R: Read + Seek> Read for ZipFileRawReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let remaining = self.end.saturating_sub(self.position);
+        if remaining == 0 || buf.is_empty() {
+            return Ok(0);
+        }
+        let len = buf.len().min(remaining as usize);
+        let n = {
+            let mut reader = self.reader.lock().map_err(|_| {
+                io::Error::new(io::ErrorKind::Other, "archive reader lock poisoned")
+            })?;
+            reader.seek(SeekFrom::Start(self.position))?;
+            reader.read(&mut buf[..len])?
+        };
+        if n == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "archive truncated inside entry data",
+            ));
+        }
+        self.position += n as u64;
+        Ok(n)
+    }
+}
+
+// 解压后的条目数据流，读到结尾时校验CRC32和大小
+pub struct ZipFileReader {
+    inner: Box<dyn Read + Send>,
+    hasher: Hasher,
+    name: String,
+    expected_crc: Option<u32>,
+    expected_size: u64,
+    bytes_read: u64,
+    verified: bool,
+}
+
+impl ZipFileReader {
+    fn verify(&mut self) -> io::Result<()> {
+        self.verified = true;
+        let actual_crc = self.hasher.clone().finalize();
+        if let Some(expected_crc) = self.expected_crc.filter(|&crc| crc != actual_crc) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                ZipError::CrcMismatch(self.name.clone(), expected_crc, actual_crc),
+            ));
+        }
+        if self.bytes_read != self.expected_size {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                ZipError::InvalidArchive(format!(
+                    "{}: size mismatch (expected {}, got {})",
+                    self.name, self.expected_size, self.bytes_read
+                )),
+            ));
+        }
+        Ok(())
+    }
 }
 
-impl ZipFile {
+impl Read for ZipFileReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        let n = self.inner.read(buf)?;
+        if n == 0 {
+            if !self.verified {
+                self.verify()?;
+            }
+            return Ok(0);
+        }
+        // 不信任解压数据流，超出中央目录记录的大小立即报错
+        if self.bytes_read + n as u64 > self.expected_size {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                ZipError::LimitExceeded(format!(
+                    "{}: decompressed data exceeds declared size {}",
+                    self.name, self.expected_size
+                )),
+            ));
+        }
+        self.hasher.update(&buf[..n]);
+        self.bytes_read += n as u64;
+        Ok(n)
+    }
+}
+
+impl<R> ZipFile<R> {
     pub fn name(&self) -> String {
         String::from_utf8_lossy(&self.header.filename).to_string()
     }
@@ -820,15 +3257,35 @@ impl ZipFile {
         self.header.file_comment = comment.as_bytes().to_vec();
     }
 
+    // 重命名条目，原样复制时本地文件头也使用新名称
+    pub fn set_name(&mut self, name: &str) {
+        self.header.filename = name.as_bytes().to_vec();
+    }
+
+    // 实际使用的压缩方法，AES加密条目从0x9901额外字段中读取
+    pub fn compression(&self) -> CompressionMethod {
+        match self.header.compression {
+            CompressionMethod::Aes => AesExtraField::parse(&self.header.extra_field)
+                .map_or(CompressionMethod::Aes, |aes| aes.compression),
+            method => method,
+        }
+    }
+
     #[allow(dead_code)]
     pub fn options(&self) -> FileOptions {
         let mut file_options = FileOptions::new();
-        file_options.compression_method = self.header.compression;
+        file_options.compression_method = self.compression();
         file_options.password = None;
-        file_options.compression_level = match self.header.compression {
+        file_options.compression_level = match self.compression() {
             CompressionMethod::Stored => 0,
-            CompressionMethod::Deflated => 6, // 默认压缩级别
-            CompressionMethod::Bzip2 => 9,    // Bzip2默认压缩级别
+            CompressionMethod::Deflated | CompressionMethod::Deflate64 => 6, // 默认压缩级别
+            CompressionMethod::Bzip2 => 9,                                   // Bzip2默认压缩级别
+            CompressionMethod::Lzma | CompressionMethod::Zstd | CompressionMethod::Xz => 6,
+            CompressionMethod::Shrunk
+            | CompressionMethod::Reduced(_)
+            | CompressionMethod::Imploded
+            | CompressionMethod::Aes
+            | CompressionMethod::Unknown(_) => 0,
         };
         file_options.modification_time = Some((self.header.mod_time, self.header.mod_date));
         file_options.external_attr = self.header.external_attr;
@@ -841,7 +3298,6 @@ impl ZipFile {
         file_options
     }
 
-    #[allow(dead_code)]
     pub fn is_dir(&self) -> bool {
         self.header.external_attr & 0x10 != 0
             || (!self.header.filename.is_empty() && *self.header.filename.last().unwrap() == b'/')
@@ -871,6 +3327,27 @@ impl ZipFile {
             .ok_or_else(|| anyhow::anyhow!("Invalid date time in zip header"))
     }
 
+    // UT额外字段中的修改时间(Unix时间戳，秒)
+    pub fn ut_modification_time(&self) -> Option<i64> {
+        let data = find_extra_field(&self.header.extra_field, UT_EXTRA_FIELD_ID)?;
+        if data.len() >= 5 && data[0] & 0x1 != 0 {
+            Some(u32::from_le_bytes(data[1..5].try_into().unwrap()) as i64)
+        } else {
+            None
+        }
+    }
+
+    // Unix权限及文件类型，仅当条目由Unix系统创建时有效
+    pub fn unix_mode(&self) -> Option<u32> {
+        let mode = self.header.external_attr >> 16;
+        (self.header.version_made >> 8 == HOST_SYSTEM_UNIX && mode != 0).then_some(mode)
+    }
+
+    pub fn is_symlink(&self) -> bool {
+        self.unix_mode()
+            .is_some_and(|mode| mode & UNIX_FILE_TYPE_MASK == UNIX_SYMLINK_TYPE)
+    }
+
     pub fn origin_size(&self) -> u64 {
         self.header.get_uncompressed_size()
     }
@@ -878,4 +3355,772 @@ impl ZipFile {
     pub fn compressed_size(&self) -> u64 {
         self.header.get_compressed_size()
     }
+
+    // 条目数据在归档中的位置(不含本地文件头)
+    pub fn data_range(&self) -> (u64, u64) {
+        (self.data_start, self.data_end)
+    }
+}
+
+impl<R: Read + Seek + Send + 'static> ZipFile<R> {
+    // 检查本地文件头与中央目录记录是否一致(-T)：文件名、压缩方法、加密标志，
+    // 没有数据描述符时还要比较CRC和大小
+    pub fn check_local_header(&self) -> Result<(), ZipError> {
+        let offset = self.header.get_local_header_offset();
+        let mut local = vec![0u8; (self.data_start - offset) as usize];
+        read_exact_at(&self.reader, &mut local, offset)?;
+        let read_u16 = |pos: usize| u16::from_le_bytes([local[pos], local[pos + 1]]);
+        let read_u32 = |pos: usize| u32::from_le_bytes(local[pos..pos + 4].try_into().unwrap());
+        let mismatch = |field: &str| {
+            ZipError::InvalidArchive(format!(
+                "{}: local header {} differs from central directory",
+                self.name(),
+                field
+            ))
+        };
+
+        let name_end = LOCAL_FILE_HEADER_SIZE + read_u16(26) as usize;
+        if local[LOCAL_FILE_HEADER_SIZE..name_end] != self.header.filename[..] {
+            return Err(mismatch("file name"));
+        }
+        if read_u16(8) != self.header.compression.id() {
+            return Err(mismatch("compression method"));
+        }
+        let flags = read_u16(6);
+        if (flags ^ self.header.flags) & ZIP_CRYPTO_FLAG != 0 {
+            return Err(mismatch("encryption flag"));
+        }
+        if flags & DATA_DESCRIPTOR_FLAG != 0 {
+            return Ok(());
+        }
+
+        if read_u32(14) != self.header.crc32 {
+            return Err(mismatch("CRC"));
+        }
+        // 本地文件头的ZIP64额外字段依次保存未压缩大小和压缩后大小
+        let zip64 = find_extra_field(&local[name_end..], ZIP64_EXTRA_FIELD_ID).unwrap_or(&[]);
+        let size = |pos: usize, index: usize| match read_u32(pos) {
+            MAX_ZIP_SIZE => zip64
+                .get(index * 8..index * 8 + 8)
+                .map(|bytes| u64::from_le_bytes(bytes.try_into().unwrap())),
+            size => Some(size as u64),
+        };
+        if size(22, 0) != Some(self.header.get_uncompressed_size()) {
+            return Err(mismatch("uncompressed size"));
+        }
+        if size(18, 1) != Some(self.header.get_compressed_size()) {
+            return Err(mismatch("compressed size"));
+        }
+        Ok(())
+    }
+
+    // 原始数据读取器，不解压也不解密
+    pub fn raw_reader(&self) -> ZipFileRawReader<R> {
+        ZipFileRawReader {
+            reader: self.reader.clone(),
+            position: self.data_start,
+            end: self.data_end,
+        }
+    }
+
+    // 加密条目的解密器，只解密不解压
+    pub fn decryptor(&self, password: &[u8]) -> Result<Box<dyn Decryptor>, ZipError> {
+        if self.header.compression == CompressionMethod::Aes {
+            let aes = AesExtraField::parse(&self.header.extra_field)?;
+            return Ok(Box::new(AesDecryptor::new(password, aes.strength)));
+        }
+        // 使用数据描述符的条目，加密头中保存的是修改时间的高字节
+        let validator = if self.header.flags & DATA_DESCRIPTOR_FLAG != 0 {
+            ZipCryptoValidator::InfoZipMsdosTime(self.header.mod_time)
+        } else {
+            ZipCryptoValidator::PkzipCrc32(self.header.crc32)
+        };
+        Ok(Box::new(ZipCryptoDecryptor::with_validator(
+            password, validator,
+        )))
+    }
+
+    // 解压(必要时解密)后的数据读取器，读完时校验CRC32
+    pub fn reader(&self, password: Option<&[u8]>) -> Result<ZipFileReader, ZipError> {
+        let aes = if self.header.compression == CompressionMethod::Aes {
+            if !self.encrypted() {
+                return Err(ZipError::InvalidArchive(format!(
+                    "{}: AES entry without encryption flag",
+                    self.name()
+                )));
+            }
+            Some(AesExtraField::parse(&self.header.extra_field)?)
+        } else {
+            None
+        };
+
+        let raw: Box<dyn Read + Send> = if self.encrypted() {
+            let password = password.ok_or(ZipError::PasswordRequired)?;
+            Box::new(DecryptionReader::new(
+                self.raw_reader(),
+                self.decryptor(password)?,
+                self.compressed_size(),
+            )?)
+        } else {
+            Box::new(self.raw_reader())
+        };
+
+        let compression = aes.map_or(self.header.compression, |aes| aes.compression);
+        let inner: Box<dyn Read + Send> = match compression {
+            CompressionMethod::Stored => raw,
+            CompressionMethod::Shrunk => Box::new(ShrinkDecoder::new(raw, self.origin_size())),
+            CompressionMethod::Reduced(factor) => {
+                Box::new(ReduceDecoder::new(raw, factor, self.origin_size())?)
+            }
+            CompressionMethod::Imploded => Box::new(ImplodeDecoder::new(
+                raw,
+                self.header.flags,
+                self.origin_size(),
+            )?),
+            CompressionMethod::Deflated => Box::new(DeflateDecoder::new(raw)),
+            CompressionMethod::Deflate64 => Box::new(Deflate64Decoder::new(raw)),
+            CompressionMethod::Bzip2 => Box::new(BzDecoder::new(raw)),
+            CompressionMethod::Lzma => Box::new(lzma_decoder(
+                raw,
+                self.header.flags & LZMA_EOS_FLAG != 0,
+                self.origin_size(),
+            )?),
+            CompressionMethod::Zstd => Box::new(zstd::stream::read::Decoder::new(raw)?),
+            CompressionMethod::Xz => Box::new(xz_decoder(raw)?),
+            method @ (CompressionMethod::Aes | CompressionMethod::Unknown(_)) => {
+                return Err(ZipError::UnsupportedFeature(format!(
+                    "{}: compression method {}",
+                    self.name(),
+                    method.id()
+                )))
+            }
+        };
+
+        Ok(ZipFileReader {
+            inner,
+            hasher: Hasher::new(),
+            name: self.name(),
+            // AE-2条目不记录CRC32
+            expected_crc: match aes {
+                Some(aes) if aes.version == AesVendorVersion::Ae2 => None,
+                _ => Some(self.header.crc32),
+            },
+            expected_size: self.origin_size(),
+            bytes_read: 0,
+            verified: false,
+        })
+    }
+}
+
+// 测试用：按 zip -fz 的格式构造存储模式的ZIP64归档，大小和偏移只记录在0x0001额外字段中，
+// 并带有ZIP64结束目录和定位器
+#[cfg(test)]
+pub(crate) fn zip64_test_archive(entries: &[(&str, &[u8])]) -> Vec<u8> {
+    let mut data = Vec::new();
+    let mut central = Vec::new();
+    for (name, content) in entries {
+        let offset = data.len() as u64;
+        let size = content.len() as u64;
+        let crc = crc32fast::hash(content);
+        let mut local_extra = ZIP64_EXTRA_FIELD_ID.to_le_bytes().to_vec();
+        local_extra.extend_from_slice(&16u16.to_le_bytes());
+        local_extra.extend_from_slice(&size.to_le_bytes());
+        local_extra.extend_from_slice(&size.to_le_bytes());
+        data.extend_from_slice(&LOCAL_FILE_HEADER_SIGNATURE.to_le_bytes());
+        data.extend_from_slice(&VERSION_NEEDED_ZIP64.to_le_bytes());
+        data.extend_from_slice(&[0; 8]); // 标志、压缩方法和修改时间
+        data.extend_from_slice(&crc.to_le_bytes());
+        data.extend_from_slice(&MAX_ZIP_SIZE.to_le_bytes());
+        data.extend_from_slice(&MAX_ZIP_SIZE.to_le_bytes());
+        data.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        data.extend_from_slice(&(local_extra.len() as u16).to_le_bytes());
+        data.extend_from_slice(name.as_bytes());
+        data.extend_from_slice(&local_extra);
+        data.extend_from_slice(content);
+
+        let mut extra = ZIP64_EXTRA_FIELD_ID.to_le_bytes().to_vec();
+        extra.extend_from_slice(&24u16.to_le_bytes());
+        extra.extend_from_slice(&size.to_le_bytes());
+        extra.extend_from_slice(&size.to_le_bytes());
+        extra.extend_from_slice(&offset.to_le_bytes());
+        central.extend_from_slice(&CENTRAL_DIR_HEADER_SIGNATURE.to_le_bytes());
+        central.extend_from_slice(&ZIP64_VERSION_MADE.to_le_bytes());
+        central.extend_from_slice(&VERSION_NEEDED_ZIP64.to_le_bytes());
+        central.extend_from_slice(&[0; 8]);
+        central.extend_from_slice(&crc.to_le_bytes());
+        central.extend_from_slice(&MAX_ZIP_SIZE.to_le_bytes());
+        central.extend_from_slice(&MAX_ZIP_SIZE.to_le_bytes());
+        central.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        central.extend_from_slice(&(extra.len() as u16).to_le_bytes());
+        central.extend_from_slice(&[0; 10]); // 注释长度、磁盘号和文件属性
+        central.extend_from_slice(&MAX_ZIP_SIZE.to_le_bytes());
+        central.extend_from_slice(name.as_bytes());
+        central.extend_from_slice(&extra);
+    }
+
+    let cd_start = data.len() as u64;
+    data.extend_from_slice(&central);
+    let end_start = data.len() as u64;
+    let zip64_end = Zip64EndOfCentralDir {
+        entries_on_disk: entries.len() as u64,
+        total_entries: entries.len() as u64,
+        central_dir_size: central.len() as u64,
+        central_dir_offset: cd_start,
+        ..Default::default()
+    };
+    data.extend_from_slice(&zip64_end.to_bytes());
+    data.extend_from_slice(&ZIP64_END_OF_CENTRAL_DIR_LOCATOR_SIGNATURE.to_le_bytes());
+    data.extend_from_slice(&0u32.to_le_bytes());
+    data.extend_from_slice(&end_start.to_le_bytes());
+    data.extend_from_slice(&1u32.to_le_bytes());
+    data.extend_from_slice(&END_OF_CENTRAL_DIR_SIGNATURE.to_le_bytes());
+    data.extend_from_slice(&[0; 4]);
+    data.extend_from_slice(&[0xFF; 12]); // 条目数、中央目录大小和偏移均由ZIP64记录给出
+    data.extend_from_slice(&0u16.to_le_bytes());
+    data
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+    use std::os::unix::fs::FileExt;
+
+    fn temp_zip_path(dir: &tempfile::TempDir, name: &str) -> String {
+        dir.path()
+            .join(format!("{}.zip", name))
+            .to_string_lossy()
+            .to_string()
+    }
+
+    #[test]
+    fn test_write_and_read_central_directory() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = temp_zip_path(&dir, "roundtrip");
+        let data = b"Hello World!\n".repeat(100);
+
+        let mut writer = ZipWriter::new(&path)?;
+        writer.set_comment("test comment");
+        writer.start_file("deflated.txt", FileOptions::new())?;
+        writer.write_all(&data)?;
+        writer.finish_file()?;
+
+        let mut options = FileOptions::new();
+        options.with_compression(CompressionMethod::Stored);
+        options.with_password("test123");
+        writer.start_file("stored.txt", options)?;
+        writer.write_all(&data)?;
+        writer.finish_file()?;
+        writer.add_directory("dir", FileOptions::new())?;
+        writer.finish()?;
+
+        let archive = ZipArchive::new(&path)?;
+        assert_eq!(archive.len(), 3);
+        assert_eq!(archive.archive_info().comment, "test comment");
+
+        let deflated = archive.by_index_raw(0)?;
+        assert_eq!(deflated.name(), "deflated.txt");
+        assert_eq!(deflated.header().compression, CompressionMethod::Deflated);
+        assert_eq!(deflated.origin_size(), data.len() as u64);
+        assert_eq!(deflated.header().crc32, crc32fast::hash(&data));
+
+        let stored = archive.by_index_raw(1)?;
+        assert!(stored.encrypted());
+        assert_eq!(
+            stored.compressed_size(),
+            data.len() as u64 + ZIP_CRYPTO_HEADER_SIZE
+        );
+
+        assert!(archive.by_index_raw(2)?.is_dir());
+
+        let mut content = Vec::new();
+        deflated.reader(None)?.read_to_end(&mut content)?;
+        assert_eq!(content, data);
+        content.clear();
+        stored.reader(Some(b"test123"))?.read_to_end(&mut content)?;
+        assert_eq!(content, data);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_end_record_comments() -> anyhow::Result<()> {
+        // 注释中包含结束目录签名，以及最长64KiB的注释
+        let fake = format!("PK\u{5}\u{6}{}tail", "\0".repeat(18));
+        for comment in [fake, "x".repeat(MAX_COMMENT_SIZE)] {
+            let mut writer = ZipWriter::from_writer(Cursor::new(Vec::new()));
+            writer.set_comment(&comment);
+            writer.start_file("a.txt", FileOptions::new())?;
+            writer.write_all(b"data")?;
+            let bytes = writer.finish()?.into_inner();
+            let archive = ZipArchive::from_reader(Cursor::new(bytes))?;
+            assert_eq!(archive.len(), 1);
+            assert_eq!(archive.archive_info().comment, comment);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_zip64_records() -> anyhow::Result<()> {
+        let bytes = zip64_test_archive(&[("a.txt", b"first"), ("b.txt", b"second")]);
+        let archive = ZipArchive::from_reader(Cursor::new(bytes))?;
+        let info = archive.archive_info();
+        assert!(info.is_zip64);
+        assert_eq!(info.num_entries, MAX_ZIP_ENTRIES);
+        assert_eq!(info.zip64_num_entries, Some(2));
+        // 大小和偏移取自0x0001额外字段
+        let second = archive.by_index_raw(1)?;
+        assert_eq!(second.header().compressed_size, MAX_ZIP_SIZE);
+        assert_eq!(second.header().get_uncompressed_size(), 6);
+        assert_eq!(second.header().get_local_header_offset(), 30 + 5 + 20 + 5);
+        let mut content = Vec::new();
+        second.reader(None)?.read_to_end(&mut content)?;
+        assert_eq!(content, b"second");
+        Ok(())
+    }
+
+    #[test]
+    fn test_auto_store_truncates_output() -> anyhow::Result<()> {
+        // bzip2压缩后变大的数据改用Store模式重写，文件和内存输出都不留残留数据
+        let mut seed = 1u32;
+        let data: Vec<u8> = (0..65536)
+            .map(|_| {
+                seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+                (seed >> 16) as u8
+            })
+            .collect();
+        let dir = tempfile::tempdir()?;
+        let path = temp_zip_path(&dir, "truncate");
+        let mut options = FileOptions::new();
+        options.with_compression(CompressionMethod::Bzip2);
+        // 固定修改时间，两次写入跨过秒边界时输出也相同
+        options.modification_time = Some((0, 0x21));
+        let mut writer = ZipWriter::new(&path)?;
+        writer.start_file("random.bin", options.clone())?;
+        writer.write_all(&data)?;
+        writer.finish()?;
+        let mut writer = ZipWriter::from_writer(Cursor::new(Vec::new()));
+        writer.start_file("random.bin", options.clone())?;
+        writer.write_all(&data)?;
+        let bytes = writer.finish()?.into_inner();
+        assert_eq!(std::fs::read(&path)?, bytes);
+
+        // 包装后的文件和借用的缓冲区同样截断
+        let mut writer = ZipWriter::from_writer(io::BufWriter::new(File::create(&path)?));
+        writer.start_file("random.bin", options.clone())?;
+        writer.write_all(&data)?;
+        writer.finish()?;
+        assert_eq!(std::fs::read(&path)?, bytes);
+        let mut buffer = Vec::new();
+        let mut writer = ZipWriter::from_writer(Cursor::new(&mut buffer));
+        writer.start_file("random.bin", options)?;
+        writer.write_all(&data)?;
+        writer.finish()?;
+        assert_eq!(buffer, bytes);
+
+        let archive = ZipArchive::from_reader(Cursor::new(bytes.clone()))?;
+        let entry = archive.by_index_raw(0)?;
+        assert_eq!(entry.header().compression, CompressionMethod::Stored);
+        assert_eq!(archive.archive_info().offset as u64, entry.data_range().1);
+        let cd_end = entry.data_range().1 + archive.archive_info().size as u64;
+        assert_eq!(bytes.len() as u64, cd_end + END_OF_CENTRAL_DIR_SIZE as u64);
+        Ok(())
+    }
+
+    #[test]
+    fn test_reader_detects_crc_mismatch() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = temp_zip_path(&dir, "crc");
+        let mut options = FileOptions::new();
+        options.with_compression(CompressionMethod::Stored);
+        let mut writer = ZipWriter::new(&path)?;
+        writer.start_file("a.txt", options)?;
+        writer.write_all(b"abcdef")?;
+        writer.finish()?;
+
+        // 篡改数据区的一个字节
+        let data_start = ZipArchive::new(&path)?.by_index_raw(0)?.data_range().0;
+        let file = std::fs::OpenOptions::new().write(true).open(&path)?;
+        file.write_all_at(b"X", data_start)?;
+
+        let entry = ZipArchive::new(&path)?.by_index_raw(0)?;
+        let err = entry
+            .reader(None)?
+            .read_to_end(&mut Vec::new())
+            .unwrap_err();
+        let zip_err = err.get_ref().and_then(|e| e.downcast_ref::<ZipError>());
+        assert!(matches!(zip_err, Some(ZipError::CrcMismatch(..))));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_limits() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = temp_zip_path(&dir, "limits");
+        let mut options = FileOptions::new();
+        options.with_compression(CompressionMethod::Stored);
+        let mut writer = ZipWriter::new(&path)?;
+        writer.start_file("a.txt", options.clone())?;
+        writer.write_all(b"hello")?;
+        writer.finish_file()?;
+        writer.start_file("zeros.bin", FileOptions::new())?;
+        writer.write_all(&[0u8; 100_000])?;
+        writer.finish()?;
+
+        let limit_err = |limits: ReadLimits| match ZipArchive::with_limits(&path, limits) {
+            Err(e) => e.downcast::<ZipError>().ok(),
+            Ok(_) => None,
+        };
+        assert!(matches!(
+            limit_err(ReadLimits {
+                max_entries: Some(1),
+                ..Default::default()
+            }),
+            Some(ZipError::LimitExceeded(_))
+        ));
+        assert!(matches!(
+            limit_err(ReadLimits {
+                max_total_uncompressed: Some(50_000),
+                ..Default::default()
+            }),
+            Some(ZipError::LimitExceeded(_))
+        ));
+        assert!(matches!(
+            limit_err(ReadLimits {
+                max_compression_ratio: Some(10),
+                ..Default::default()
+            }),
+            Some(ZipError::LimitExceeded(_))
+        ));
+
+        // 让第二个条目的本地头偏移指向第一个条目，并把第一个条目的声明大小改小
+        let bytes = std::fs::read(&path)?;
+        let sig = CENTRAL_DIR_HEADER_SIGNATURE.to_le_bytes();
+        let cd_positions: Vec<usize> = bytes
+            .windows(4)
+            .enumerate()
+            .filter(|(_, w)| *w == sig)
+            .map(|(i, _)| i)
+            .collect();
+        let file = std::fs::OpenOptions::new().write(true).open(&path)?;
+        file.write_all_at(&0u32.to_le_bytes(), cd_positions[1] as u64 + 42)?;
+        file.write_all_at(&1u32.to_le_bytes(), cd_positions[0] as u64 + 24)?;
+
+        assert!(matches!(
+            limit_err(ReadLimits {
+                max_overlapping_entries: Some(0),
+                ..Default::default()
+            }),
+            Some(ZipError::OverlappingEntries(..))
+        ));
+
+        let entry = ZipArchive::new(&path)?.by_index_raw(0)?;
+        let err = entry
+            .reader(None)?
+            .read_to_end(&mut Vec::new())
+            .unwrap_err();
+        let zip_err = err.get_ref().and_then(|e| e.downcast_ref::<ZipError>());
+        assert!(matches!(zip_err, Some(ZipError::LimitExceeded(_))));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_archive_from_memory_concurrent_reads() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = temp_zip_path(&dir, "memory");
+        let first = b"first entry\n".repeat(1000);
+        let second = b"second entry\n".repeat(1000);
+        let mut writer = ZipWriter::new(&path)?;
+        writer.start_file("first.txt", FileOptions::new())?;
+        writer.write_all(&first)?;
+        writer.finish_file()?;
+        writer.start_file("second.txt", FileOptions::new())?;
+        writer.write_all(&second)?;
+        writer.finish()?;
+        let bytes = std::fs::read(&path)?;
+
+        let archive = ZipArchive::from_reader(Cursor::new(bytes))?;
+        assert_eq!(archive.len(), 2);
+
+        // 两个条目在不同线程中交替读取，共享同一个数据源
+        let handles: Vec<_> = [(0, first), (1, second)]
+            .into_iter()
+            .map(|(index, expected)| {
+                let entry = archive.by_index_raw(index).unwrap();
+                std::thread::spawn(move || {
+                    let mut reader = entry.reader(None).unwrap();
+                    let mut content = Vec::new();
+                    let mut chunk = [0u8; 512];
+                    loop {
+                        let n = reader.read(&mut chunk).unwrap();
+                        if n == 0 {
+                            break;
+                        }
+                        content.extend_from_slice(&chunk[..n]);
+                        std::thread::yield_now();
+                    }
+                    assert_eq!(content, expected);
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_stream_writer_uses_data_descriptors() -> anyhow::Result<()> {
+        let data = b"streamed data\n".repeat(200);
+        let mut writer = ZipWriter::new_stream(Vec::new());
+        writer.start_file("plain.txt", FileOptions::new())?;
+        writer.write_all(&data)?;
+        writer.finish_file()?;
+        let mut options = FileOptions::new();
+        options.with_password("secret");
+        options.large_file = true;
+        writer.start_file("secret.txt", options)?;
+        writer.write_all(&data)?;
+        let bytes = writer.finish()?.into_inner();
+
+        let archive = ZipArchive::from_reader(Cursor::new(bytes))?;
+        for (index, password) in [(0, None), (1, Some(&b"secret"[..]))] {
+            let entry = archive.by_index_raw(index)?;
+            assert_ne!(entry.header().flags & DATA_DESCRIPTOR_FLAG, 0);
+            let mut content = Vec::new();
+            entry.reader(password)?.read_to_end(&mut content)?;
+            assert_eq!(content, data);
+        }
+
+        // 可定位的内存输出仍然回填本地文件头
+        let mut writer = ZipWriter::from_writer(Cursor::new(Vec::new()));
+        writer.start_file("plain.txt", FileOptions::new())?;
+        writer.write_all(&data)?;
+        let bytes = writer.finish()?.into_inner();
+        let entry = ZipArchive::from_reader(Cursor::new(bytes))?.by_index_raw(0)?;
+        assert_eq!(entry.header().flags & DATA_DESCRIPTOR_FLAG, 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_zstd_round_trip_and_unknown_method() -> anyhow::Result<()> {
+        let data = b"zstd compressed build output\n".repeat(500);
+        let mut writer = ZipWriter::from_writer(Cursor::new(Vec::new()));
+        let mut options = FileOptions::new();
+        options.with_compression(CompressionMethod::Zstd);
+        writer.start_file("plain.zst", options.clone())?;
+        writer.write_all(&data)?;
+        options.with_password("secret");
+        writer.start_file("secret.zst", options)?;
+        writer.write_all(&data)?;
+        let mut bytes = writer.finish()?.into_inner();
+
+        let archive = ZipArchive::from_reader(Cursor::new(bytes.clone()))?;
+        for (index, password) in [(0, None), (1, Some(&b"secret"[..]))] {
+            let entry = archive.by_index_raw(index)?;
+            assert_eq!(entry.header().compression, CompressionMethod::Zstd);
+            assert_eq!(entry.header().version_needed, 63);
+            let mut content = Vec::new();
+            entry.reader(password)?.read_to_end(&mut content)?;
+            assert_eq!(content, data);
+        }
+
+        // 把第一个条目的中央目录压缩方法改成未知编号
+        let cd_offset = archive.archive_info().offset as usize;
+        bytes[cd_offset + 10..cd_offset + 12].copy_from_slice(&77u16.to_le_bytes());
+        let entry = ZipArchive::from_reader(Cursor::new(bytes))?.by_index_raw(0)?;
+        assert_eq!(entry.header().compression, CompressionMethod::Unknown(77));
+        assert!(matches!(
+            entry.reader(None),
+            Err(ZipError::UnsupportedFeature(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_lzma_and_xz_round_trip() -> anyhow::Result<()> {
+        let data = b"lzma and xz payload\n".repeat(500);
+        let mut writer = ZipWriter::from_writer(Cursor::new(Vec::new()));
+        for method in [CompressionMethod::Lzma, CompressionMethod::Xz] {
+            let mut options = FileOptions::new();
+            options.with_compression(method);
+            writer.start_file(&format!("{}.txt", method), options.clone())?;
+            writer.write_all(&data)?;
+            options.with_password("secret");
+            writer.start_file(&format!("{}-encrypted.txt", method), options)?;
+            writer.write_all(&data)?;
+        }
+        let bytes = writer.finish()?.into_inner();
+
+        let archive = ZipArchive::from_reader(Cursor::new(bytes))?;
+        for index in 0..archive.len() {
+            let entry = archive.by_index_raw(index)?;
+            let is_lzma = entry.header().compression == CompressionMethod::Lzma;
+            assert_eq!(entry.header().flags & LZMA_EOS_FLAG != 0, is_lzma);
+            let password = entry.encrypted().then_some(&b"secret"[..]);
+            let mut content = Vec::new();
+            entry.reader(password)?.read_to_end(&mut content)?;
+            assert_eq!(content, data);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_deflate64_read_and_raw_copy() -> anyhow::Result<()> {
+        // 不含长度258匹配的普通deflate流同时也是合法的Deflate64流
+        let data: Vec<u8> = (0..2000)
+            .flat_map(|i| format!("line {}\n", i).into_bytes())
+            .collect();
+        let mut writer = ZipWriter::from_writer(Cursor::new(Vec::new()));
+        writer.start_file("big.txt", FileOptions::new())?;
+        writer.write_all(&data)?;
+        let mut bytes = writer.finish()?.into_inner();
+        let cd_offset = ZipArchive::from_reader(Cursor::new(bytes.clone()))?
+            .archive_info()
+            .offset as usize;
+        for offset in [8, cd_offset + 10] {
+            bytes[offset..offset + 2].copy_from_slice(&9u16.to_le_bytes());
+        }
+
+        let archive = ZipArchive::from_reader(Cursor::new(bytes))?;
+        let entry = archive.by_index_raw(0)?;
+        assert_eq!(entry.header().compression, CompressionMethod::Deflate64);
+        let mut content = Vec::new();
+        entry.reader(None)?.read_to_end(&mut content)?;
+        assert_eq!(content, data);
+
+        // 只读方法不能用于写入，但可以原样复制
+        let mut writer = ZipWriter::from_writer(Cursor::new(Vec::new()));
+        let mut options = FileOptions::new();
+        options.with_compression(CompressionMethod::Deflate64);
+        assert!(writer.start_file("new.txt", options).is_err());
+        let mut writer = ZipWriter::from_writer(Cursor::new(Vec::new()));
+        writer.raw_copy_file(&entry)?;
+        let copied = ZipArchive::from_reader(Cursor::new(writer.finish()?.into_inner()))?;
+        let entry = copied.by_index_raw(0)?;
+        assert_eq!(entry.header().compression, CompressionMethod::Deflate64);
+        let mut content = Vec::new();
+        entry.reader(None)?.read_to_end(&mut content)?;
+        assert_eq!(content, data);
+        Ok(())
+    }
+
+    #[test]
+    fn test_aes_round_trip() -> anyhow::Result<()> {
+        use crate::encryption::aes::{AesStrength, AesVendorVersion};
+        let data = b"hello aes\n".repeat(100);
+        let mut writer = ZipWriter::from_writer(Cursor::new(Vec::new()));
+        for (name, version) in [
+            ("ae1.txt", AesVendorVersion::Ae1),
+            ("ae2.txt", AesVendorVersion::Ae2),
+        ] {
+            let mut options = FileOptions::new();
+            options.with_password("secret");
+            options.with_encryption(EncryptionMethod::Aes(AesStrength::Aes256, version));
+            writer.start_file(name, options)?;
+            writer.write_all(&data)?;
+        }
+        let archive = ZipArchive::from_reader(Cursor::new(writer.finish()?.into_inner()))?;
+
+        for (index, crc_kept) in [(0, true), (1, false)] {
+            let entry = archive.by_index_raw(index)?;
+            // 方法字段为99，实际压缩方法记录在AES额外字段中
+            assert_eq!(entry.header().compression, CompressionMethod::Aes);
+            assert_eq!(entry.compression(), CompressionMethod::Deflated);
+            assert_eq!(entry.header().version_needed, 51);
+            assert_eq!(entry.header().crc32 != 0, crc_kept);
+            let mut content = Vec::new();
+            entry.reader(Some(b"secret"))?.read_to_end(&mut content)?;
+            assert_eq!(content, data);
+            assert!(entry.reader(Some(b"wrong")).is_err());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_split_writer() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("out.zip");
+        let config = SplitConfig {
+            size: 1000,
+            ..Default::default()
+        };
+        fn write_entries<W: ZipSink>(writer: &mut ZipWriter<W>) -> anyhow::Result<()> {
+            for i in 0..5 {
+                let mut options = FileOptions::new();
+                options.with_compression(CompressionMethod::Stored);
+                options.modification_time = Some((0, 0x21));
+                writer.start_file(&format!("f{}", i), options)?;
+                writer.write_all(&[b'a' + i as u8; 700])?;
+            }
+            Ok(())
+        }
+        let mut writer = ZipWriter::new_split(&path.to_string_lossy(), config)?;
+        write_entries(&mut writer)?;
+        let headers = writer.entries().to_vec();
+        writer.finish()?;
+
+        let volumes: Vec<Vec<u8>> = (0..4)
+            .map(|disk| std::fs::read(volume_path(&path, disk)))
+            .chain([std::fs::read(&path)])
+            .collect::<io::Result<_>>()?;
+        assert!(!volume_path(&path, 4).exists());
+        assert_eq!(volumes[0][..4], SPLIT_SIGNATURE.to_le_bytes());
+        assert!(volumes.iter().all(|volume| volume.len() <= 1000));
+        // 本地文件头完整地位于记录的分卷中
+        for header in &headers {
+            let volume = &volumes[header.disk_num as usize];
+            let start = header.local_header_offset as usize;
+            let end = start + LOCAL_FILE_HEADER_SIZE + header.filename.len();
+            assert!(end <= volume.len());
+            assert_eq!(
+                volume[start..start + 4],
+                LOCAL_FILE_HEADER_SIGNATURE.to_le_bytes()
+            );
+            assert_eq!(volume[start + 30..end], header.filename[..]);
+        }
+        // 结束目录记录位于最后一个分卷，记录当前分卷号
+        let last = &volumes[4];
+        let eocd = &last[last.len() - END_OF_CENTRAL_DIR_SIZE..];
+        assert_eq!(eocd[..4], END_OF_CENTRAL_DIR_SIGNATURE.to_le_bytes());
+        assert_eq!(u16::from_le_bytes([eocd[4], eocd[5]]), 4);
+        assert_eq!(u16::from_le_bytes([eocd[10], eocd[11]]), 5);
+
+        // 读取时自动打开全部分卷，跨分卷的条目数据可以连续读取
+        let archive = ZipArchive::new(&path.to_string_lossy())?;
+        assert_eq!(archive.split_files().map(|files| files.len()), Some(5));
+        for i in 0..5 {
+            let mut content = Vec::new();
+            archive
+                .by_index_raw(i)?
+                .reader(None)?
+                .read_to_end(&mut content)?;
+            assert_eq!(content, [b'a' + i as u8; 700]);
+        }
+
+        // 包装在BufWriter中的分卷输出仍然不跨分卷写记录，并在结束时改名最后一个分卷
+        let wrapped = dir.path().join("wrapped.zip");
+        let split = SplitWriter::new(&wrapped, config)?;
+        let mut writer = ZipWriter::from_writer(io::BufWriter::new(split));
+        assert!(writer.is_streaming());
+        write_entries(&mut writer)?;
+        writer.finish()?;
+        for disk in 0..4 {
+            assert_eq!(
+                std::fs::read(volume_path(&wrapped, disk))?,
+                volumes[disk as usize]
+            );
+        }
+        assert_eq!(std::fs::read(&wrapped)?, volumes[4]);
+
+        std::fs::rename(volume_path(&path, 2), dir.path().join("moved"))?;
+        let error = ZipArchive::new(&path.to_string_lossy()).unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<ZipError>(),
+            Some(ZipError::MissingVolume(_))
+        ));
+        Ok(())
+    }
 }
@@ -0,0 +1,188 @@
+/*
+ * SPDX-FileCopyrightText: 2025 UnionTech Software Technology Co., Ltd.
+ *
+ * SPDX-License-Identifier: GPL-2.0-or-later
+ */
+
+// 对已有归档中的条目加密或解密，只处理加密层，不重新压缩
+use crate::cli;
+use crate::encryption::zipcrypt::ZipCryptoEncryptor;
+use crate::encryption::DecryptionReader;
+use crate::error::{ZipCloakError, ZipError};
+use crate::utils::common::{safe_move_file, temp_archive_path};
+use crate::utils::log::LogConfig;
+use crate::zip::{
+    CompressionMethod, ZipArchive, ZipFile, ZipWriter, DATA_DESCRIPTOR_FLAG, ZIP_CRYPTO_FLAG,
+    ZIP_CRYPTO_HEADER_SIZE,
+};
+use anyhow::Result;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+pub struct ZipCloak<'a> {
+    archive: ZipArchive,
+    zip_path: PathBuf,
+    args: &'a cli::ZipCloakArgs,
+}
+
+impl<'a> ZipCloak<'a> {
+    pub fn new(args: &'a cli::ZipCloakArgs) -> Result<Self> {
+        let zip_path = args
+            .zipfile
+            .clone()
+            .ok_or_else(|| ZipCloakError::InvalidArguments("missing zipfile".to_string()))?;
+        if !zip_path.exists() {
+            return Err(ZipCloakError::ArchiveNotFound(zip_path.display().to_string()).into());
+        }
+        let archive = ZipArchive::new(&zip_path.to_string_lossy())?;
+        Ok(Self {
+            archive,
+            zip_path,
+            args,
+        })
+    }
+
+    // 需要处理的条目数：加密时为未加密的条目，解密时为已加密的条目
+    pub fn pending_entries(&self) -> Result<usize> {
+        let mut count = 0;
+        for index in 0..self.archive.len() {
+            let file = self.archive.by_index_raw(index)?;
+            if file.encrypted() == self.args.decrypt && !file.is_dir() {
+                count += 1;
+            }
+        }
+        Ok(count)
+    }
+
+    // 写入临时文件，全部成功后再替换原归档(或写入 -O 指定的文件)
+    pub fn run(&self, password: &str) -> Result<()> {
+        let output = self
+            .args
+            .out
+            .clone()
+            .unwrap_or_else(|| self.zip_path.clone());
+        let temp_path = temp_archive_path(&output, self.args.temp_path.as_deref());
+        log::debug!("zipcloak: writing {}", temp_path.display());
+
+        if let Err(e) = self.write_archive(&temp_path, password) {
+            let _ = fs::remove_file(&temp_path);
+            return Err(e);
+        }
+        if let Ok(metadata) = fs::metadata(&self.zip_path) {
+            fs::set_permissions(&temp_path, metadata.permissions())?;
+        }
+        safe_move_file(&temp_path, &output)
+    }
+
+    fn write_archive(&self, temp_path: &Path, password: &str) -> Result<()> {
+        let mut writer = ZipWriter::new(&temp_path.to_string_lossy())?;
+        writer.set_comment(&self.archive.archive_info().comment);
+        for index in 0..self.archive.len() {
+            let file = self.archive.by_index_raw(index)?;
+            if file.is_dir() || file.encrypted() != self.args.decrypt {
+                writer.raw_copy_file(&file)?;
+            } else if self.args.decrypt {
+                self.decrypt_file(&mut writer, &file, password)?;
+            } else {
+                LogConfig::println(&format!("   encrypting: {}", file.name()));
+                encrypt_file(&mut writer, &file, password)?;
+            }
+        }
+        writer.finish()?;
+        Ok(())
+    }
+
+    fn decrypt_file(&self, writer: &mut ZipWriter, file: &ZipFile, password: &str) -> Result<()> {
+        // AES条目的CRC和压缩方法都记录在0x9901字段中，无法只去掉加密层
+        if file.header().compression == CompressionMethod::Aes {
+            log::warn!("{}: AES encrypted, just copying", file.name());
+            writer.raw_copy_file(file)?;
+            return Ok(());
+        }
+        let decryptor = file.decryptor(password.as_bytes())?;
+        let mut reader =
+            match DecryptionReader::new(file.raw_reader(), decryptor, file.compressed_size()) {
+                Ok(reader) => reader,
+                Err(ZipError::InvalidPassword) => {
+                    LogConfig::println(&format!(
+                        "   {}: wrong password -- just copying",
+                        file.name()
+                    ));
+                    writer.raw_copy_file(file)?;
+                    return Ok(());
+                }
+                Err(e) => return Err(e.into()),
+            };
+
+        LogConfig::println(&format!("   decrypting: {}", file.name()));
+        let mut header = file.header().clone();
+        header.flags &= !ZIP_CRYPTO_FLAG;
+        header.set_compressed_size(file.compressed_size() - ZIP_CRYPTO_HEADER_SIZE);
+        writer.raw_write_file(header, |sink| io::copy(&mut reader, sink))?;
+        Ok(())
+    }
+}
+
+fn encrypt_file(writer: &mut ZipWriter, file: &ZipFile, password: &str) -> Result<()> {
+    let mut header = file.header().clone();
+    // 与读取时的校验方式一致：使用数据描述符的条目用修改时间校验密码
+    let verifier = if header.flags & DATA_DESCRIPTOR_FLAG != 0 {
+        (header.mod_time as u32) << 16
+    } else {
+        header.crc32
+    };
+    header.flags |= ZIP_CRYPTO_FLAG;
+    header.version_needed = header.version_needed.max(20);
+    header.set_compressed_size(file.compressed_size() + ZIP_CRYPTO_HEADER_SIZE);
+
+    let mut reader = file.raw_reader();
+    writer.raw_write_file(header, |sink| {
+        let mut encryptor = ZipCryptoEncryptor::new(sink, password, verifier)?;
+        let copied = io::copy(&mut reader, &mut encryptor)?;
+        encryptor.finish()?;
+        Ok(copied + ZIP_CRYPTO_HEADER_SIZE)
+    })?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::zip::FileOptions;
+    use std::io::{Read, Write};
+
+    fn read_entry(path: &Path, password: Option<&[u8]>) -> Result<(bool, Vec<u8>)> {
+        let archive = ZipArchive::new(&path.to_string_lossy())?;
+        let file = archive.by_index_raw(0)?;
+        let mut content = Vec::new();
+        file.reader(password)?.read_to_end(&mut content)?;
+        Ok((file.encrypted(), content))
+    }
+
+    #[test]
+    fn test_encrypt_and_decrypt_in_place() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("a.zip");
+        let data = b"cloak me\n".repeat(200);
+        let mut writer = ZipWriter::new(&path.to_string_lossy())?;
+        writer.start_file("a.txt", FileOptions::new())?;
+        writer.write_all(&data)?;
+        writer.finish()?;
+
+        let mut args = cli::ZipCloakArgs {
+            zipfile: Some(path.clone()),
+            ..Default::default()
+        };
+        ZipCloak::new(&args)?.run("secret")?;
+        assert_eq!(read_entry(&path, Some(b"secret"))?, (true, data.clone()));
+
+        // 密码错误时原样复制
+        args.decrypt = true;
+        ZipCloak::new(&args)?.run("wrong")?;
+        assert!(read_entry(&path, None).is_err());
+        ZipCloak::new(&args)?.run("secret")?;
+        assert_eq!(read_entry(&path, None)?, (false, data));
+        Ok(())
+    }
+}
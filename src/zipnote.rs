@@ -0,0 +1,272 @@
+/*
+ * SPDX-FileCopyrightText: 2025 UnionTech Software Technology Co., Ltd.
+ *
+ * SPDX-License-Identifier: GPL-2.0-or-later
+ */
+
+// 与Info-ZIP zipnote兼容的注释格式：
+//   @ name                              条目名称
+//   @=newname                           可选，重命名条目(也支持 "@ name=newname")
+//   comment...                          条目注释，可以有多行
+//   @ (comment above this line)
+//   ...
+//   @ (zip file comment below this line)
+//   zip file comment...                 归档注释直到输入结束
+use crate::cli;
+use crate::error::ZipNoteError;
+use crate::utils::common::{safe_move_file, temp_archive_path};
+use crate::zip::{ZipArchive, ZipWriter};
+use anyhow::Result;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+const COMMENT_END: &str = "@ (comment above this line)";
+const ZIP_COMMENT_START: &str = "@ (zip file comment below this line)";
+
+// 从注释文件中解析出的单个条目信息
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct EntryNote {
+    pub new_name: Option<String>,
+    pub comment: String,
+}
+
+// 按条目顺序解析注释文件，names 为归档中的条目名称
+pub fn parse_notes(input: &str, names: &[String]) -> Result<(Vec<EntryNote>, String)> {
+    let invalid = |message: String| ZipNoteError::InvalidCommentFormat(message);
+    let mut lines = input.lines().peekable();
+    let mut notes = Vec::with_capacity(names.len());
+
+    for name in names {
+        let line = lines
+            .next()
+            .ok_or_else(|| invalid(format!("missing entry for {}", name)))?;
+        let listed = line
+            .strip_prefix("@ ")
+            .ok_or_else(|| invalid(format!("expected \"@ {}\", got \"{}\"", name, line)))?;
+        let mut note = EntryNote::default();
+        if listed != name {
+            // "@ old=new" 形式的重命名
+            match listed
+                .strip_prefix(name.as_str())
+                .and_then(|rest| rest.strip_prefix('='))
+            {
+                Some(new_name) => note.new_name = Some(new_name.to_string()),
+                None => {
+                    return Err(invalid(format!("name mismatch: {} != {}", listed, name)).into())
+                }
+            }
+        }
+        if let Some(new_name) = lines.peek().and_then(|line| line.strip_prefix("@=")) {
+            note.new_name = Some(new_name.to_string());
+            lines.next();
+        }
+        if note.new_name.as_deref() == Some("") {
+            return Err(invalid(format!("empty new name for {}", name)).into());
+        }
+
+        let mut comment = Vec::new();
+        loop {
+            match lines.next() {
+                Some(line) if line.starts_with('@') => {
+                    if line != COMMENT_END {
+                        return Err(invalid(format!(
+                            "expected \"{}\" after {}",
+                            COMMENT_END, name
+                        ))
+                        .into());
+                    }
+                    break;
+                }
+                Some(line) => comment.push(line),
+                None => return Err(invalid(format!("unterminated comment for {}", name)).into()),
+            }
+        }
+        note.comment = comment.join("\n");
+        notes.push(note);
+    }
+
+    // 归档注释部分可以省略，省略时清空归档注释
+    let zip_comment = match lines.next() {
+        Some(ZIP_COMMENT_START) => lines.collect::<Vec<_>>().join("\n"),
+        Some(line) => return Err(invalid(format!("unexpected line \"{}\"", line)).into()),
+        None => String::new(),
+    };
+    Ok((notes, zip_comment))
+}
+
+pub struct ZipNote<'a> {
+    archive: ZipArchive,
+    zip_path: PathBuf,
+    args: &'a cli::ZipNoteArgs,
+}
+
+impl<'a> ZipNote<'a> {
+    pub fn new(args: &'a cli::ZipNoteArgs) -> Result<Self> {
+        let zip_path = args
+            .zipfile
+            .clone()
+            .ok_or_else(|| ZipNoteError::InvalidArguments("missing zipfile".to_string()))?;
+        if !zip_path.exists() {
+            return Err(ZipNoteError::ArchiveNotFound(zip_path.display().to_string()).into());
+        }
+        let archive = ZipArchive::new(&zip_path.to_string_lossy())?;
+        Ok(Self {
+            archive,
+            zip_path,
+            args,
+        })
+    }
+
+    // 输出全部条目注释和归档注释
+    pub fn dump<W: Write>(&self, out: &mut W) -> Result<()> {
+        for index in 0..self.archive.len() {
+            let file = self.archive.by_index_raw(index)?;
+            writeln!(out, "@ {}", file.name())?;
+            let comment = file.comments();
+            if !comment.is_empty() {
+                writeln!(out, "{}", comment)?;
+            }
+            writeln!(out, "{}", COMMENT_END)?;
+        }
+        writeln!(out, "{}", ZIP_COMMENT_START)?;
+        let zip_comment = &self.archive.archive_info().comment;
+        if !zip_comment.is_empty() {
+            writeln!(out, "{}", zip_comment)?;
+        }
+        Ok(())
+    }
+
+    // 按注释文件更新条目注释、名称和归档注释，写入临时文件后替换原归档
+    pub fn update(&self, input: &str) -> Result<()> {
+        let names = (0..self.archive.len())
+            .map(|index| Ok(self.archive.by_index_raw(index)?.name()))
+            .collect::<Result<Vec<_>>>()?;
+        let (notes, zip_comment) = parse_notes(input, &names)?;
+
+        let temp_path = temp_archive_path(&self.zip_path, self.args.temp_path.as_deref());
+        if let Err(e) = self.write_archive(&temp_path, &notes, &zip_comment) {
+            let _ = fs::remove_file(&temp_path);
+            return Err(e);
+        }
+        if let Ok(metadata) = fs::metadata(&self.zip_path) {
+            fs::set_permissions(&temp_path, metadata.permissions())?;
+        }
+        safe_move_file(&temp_path, &self.zip_path)
+    }
+
+    fn write_archive(
+        &self,
+        temp_path: &Path,
+        notes: &[EntryNote],
+        zip_comment: &str,
+    ) -> Result<()> {
+        let mut writer = ZipWriter::new(&temp_path.to_string_lossy())?;
+        writer.set_comment(zip_comment);
+        for (index, note) in notes.iter().enumerate() {
+            let mut file = self.archive.by_index_raw(index)?;
+            file.set_comments(&note.comment);
+            if let Some(new_name) = &note.new_name {
+                log::debug!("zipnote: renaming {} to {}", file.name(), new_name);
+                file.set_name(new_name);
+            }
+            writer.raw_copy_file(&file)?;
+        }
+        writer.finish()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_notes() -> Result<()> {
+        let names = vec![
+            "a.txt".to_string(),
+            "b.txt".to_string(),
+            "c.txt".to_string(),
+        ];
+        let input = "@ a.txt\nfirst line\nsecond line\n@ (comment above this line)\n\
+                     @ b.txt=docs/b.txt\n@ (comment above this line)\n\
+                     @ c.txt\n@=c.md\nnote\n@ (comment above this line)\n\
+                     @ (zip file comment below this line)\nrelease 1.0\n";
+        let (notes, zip_comment) = parse_notes(input, &names)?;
+        assert_eq!(notes[0].comment, "first line\nsecond line");
+        assert_eq!(notes[0].new_name, None);
+        assert_eq!(notes[1].comment, "");
+        assert_eq!(notes[1].new_name.as_deref(), Some("docs/b.txt"));
+        assert_eq!(notes[2].new_name.as_deref(), Some("c.md"));
+        assert_eq!(notes[2].comment, "note");
+        assert_eq!(zip_comment, "release 1.0");
+
+        assert!(parse_notes("@ x.txt\n@ (comment above this line)\n", &names[..1]).is_err());
+        assert!(parse_notes("@ a.txt\nno end\n", &names[..1]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_update_and_dump() -> Result<()> {
+        use crate::zip::FileOptions;
+        use std::io::Read;
+
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("notes.zip");
+        let mut writer = ZipWriter::new(&path.to_string_lossy())?;
+        for name in ["a.txt", "b.txt", "c.txt"] {
+            writer.start_file(name, FileOptions::new())?;
+            writer.write_all(name.as_bytes())?;
+        }
+        writer.finish()?;
+        let args = cli::ZipNoteArgs {
+            zipfile: Some(path.clone()),
+            ..Default::default()
+        };
+
+        let input = "@ a.txt\nfirst line\nsecond line\n@ (comment above this line)\n\
+                     @ b.txt=docs/b.txt\n@ (comment above this line)\n\
+                     @ c.txt\n@=c.md\nnote\n@ (comment above this line)\n\
+                     @ (zip file comment below this line)\nrelease 1.0\n";
+        ZipNote::new(&args)?.update(input)?;
+
+        // 中央目录和本地文件头中的名称都已更新，数据不变
+        let archive = ZipArchive::new(&path.to_string_lossy())?;
+        let bytes = fs::read(&path)?;
+        assert_eq!(archive.archive_info().comment, "release 1.0");
+        let expected = [
+            ("a.txt", "a.txt", "first line\nsecond line"),
+            ("docs/b.txt", "b.txt", ""),
+            ("c.md", "c.txt", "note"),
+        ];
+        for (index, (name, data, comment)) in expected.into_iter().enumerate() {
+            let file = archive.by_index_raw(index)?;
+            assert_eq!(file.name(), name);
+            assert_eq!(file.comments(), comment);
+            let offset = file.header().get_local_header_offset() as usize;
+            assert_eq!(
+                &bytes[offset + 30..offset + 30 + name.len()],
+                name.as_bytes()
+            );
+            file.check_local_header()?;
+            let mut content = String::new();
+            file.reader(None)?.read_to_string(&mut content)?;
+            assert_eq!(content, data);
+        }
+
+        // dump 的输出再写回去，归档内容不变
+        let mut dumped = Vec::new();
+        ZipNote::new(&args)?.dump(&mut dumped)?;
+        let dumped = String::from_utf8(dumped)?;
+        assert_eq!(
+            dumped,
+            "@ a.txt\nfirst line\nsecond line\n@ (comment above this line)\n\
+             @ docs/b.txt\n@ (comment above this line)\n\
+             @ c.md\nnote\n@ (comment above this line)\n\
+             @ (zip file comment below this line)\nrelease 1.0\n"
+        );
+        ZipNote::new(&args)?.update(&dumped)?;
+        assert_eq!(fs::read(&path)?, bytes);
+        Ok(())
+    }
+}
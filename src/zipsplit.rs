@@ -3,28 +3,315 @@
  *
  * SPDX-License-Identifier: GPL-2.0-or-later
  */
+
+// 将归档拆分为若干个可以单独解压的小归档，条目原样复制不重新压缩
 use crate::cli;
 use crate::error::ZipSplitError;
-use crate::zip::ZipArchive;
-use anyhow::{Ok, Result};
+use crate::utils::log::LogConfig;
+use crate::zip::{ZipArchive, ZipWriter, END_OF_CENTRAL_DIR_SIZE};
+use anyhow::Result;
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::path::PathBuf;
+
+pub const INDEX_FILE_NAME: &str = "zipsplit.idx";
+
+// 待拆分的条目
+#[derive(Debug, Clone)]
+pub struct SplitEntry {
+    pub index: usize,
+    pub name: String,
+    // 条目在输出归档中占用的字节数(本地文件头+数据+中央目录记录)
+    pub size: u64,
+}
 
 pub struct ZipSplitter<'a> {
     archive: ZipArchive,
+    zip_path: PathBuf,
     args: &'a cli::ZipSplitArgs,
 }
 
 impl<'a> ZipSplitter<'a> {
     pub fn new(args: &'a cli::ZipSplitArgs) -> Result<Self> {
-        let zip_path = args.zipfile.clone().unwrap();
+        let zip_path = args
+            .zipfile
+            .clone()
+            .ok_or_else(|| ZipSplitError::InvalidArguments("missing zipfile".to_string()))?;
         if !zip_path.exists() {
             log::error!("Zip file not found: {}", zip_path.display());
             return Err(ZipSplitError::ArchiveNotFound(zip_path.display().to_string()).into());
         }
-        let archive = ZipArchive::new(zip_path.to_str().unwrap())?;
-        Ok(Self { archive, args })
+        let archive = ZipArchive::new(&zip_path.to_string_lossy())?;
+        Ok(Self {
+            archive,
+            zip_path,
+            args,
+        })
+    }
+
+    pub fn entries(&self) -> Result<Vec<SplitEntry>> {
+        (0..self.archive.len())
+            .map(|index| {
+                let file = self.archive.by_index_raw(index)?;
+                Ok(SplitEntry {
+                    index,
+                    name: file.name(),
+                    size: file.header().raw_copy_size(),
+                })
+            })
+            .collect()
+    }
+
+    // 每个输出归档可用于条目的空间，第一个归档还要扣除 -r 预留的空间和索引文件
+    fn capacities(&self, index_size: u64) -> Result<(u64, u64)> {
+        let max_size = self.args.max_size as u64;
+        let capacity = max_size
+            .checked_sub(END_OF_CENTRAL_DIR_SIZE as u64)
+            .ok_or_else(|| {
+                ZipSplitError::InvalidArguments(format!("max size {} too small", max_size))
+            })?;
+        let reserved = self.args.room as u64 + index_size;
+        Ok((capacity.saturating_sub(reserved), capacity))
+    }
+
+    // 计算每个输出归档包含的条目
+    // 索引文件的大小取决于拆分结果，需要重新计算直到预留的空间足够
+    pub fn plan(&self, entries: &[SplitEntry]) -> Result<Vec<Vec<usize>>> {
+        let mut index_size = 0;
+        loop {
+            let pieces = self.plan_with(entries, index_size)?;
+            if !self.args.index {
+                return Ok(pieces);
+            }
+            let needed = self.index_content(entries, &pieces).len() as u64;
+            if needed <= index_size {
+                return Ok(pieces);
+            }
+            index_size = needed;
+        }
+    }
+
+    // 默认按大小降序放入剩余空间最小且能容纳的归档(best-fit decreasing)，-s 时只按顺序依次填充
+    // 每个归档内的条目保持原归档中的顺序
+    fn plan_with(&self, entries: &[SplitEntry], index_size: u64) -> Result<Vec<Vec<usize>>> {
+        let (first_capacity, capacity) = self.capacities(index_size)?;
+        let capacity_of = |piece: usize| if piece == 0 { first_capacity } else { capacity };
+
+        let mut order: Vec<usize> = (0..entries.len()).collect();
+        if !self.args.sequential {
+            order.sort_by(|&a, &b| entries[b].size.cmp(&entries[a].size));
+        }
+
+        let mut pieces: Vec<Vec<usize>> = Vec::new();
+        let mut used: Vec<u64> = Vec::new();
+        for position in order {
+            let entry = &entries[position];
+            if entry.size > capacity {
+                return Err(ZipSplitError::EntryTooLarge(format!(
+                    "{} needs {} bytes, max is {}",
+                    entry.name,
+                    entry.size + END_OF_CENTRAL_DIR_SIZE as u64,
+                    self.args.max_size
+                ))
+                .into());
+            }
+            let candidates = if self.args.sequential {
+                pieces.len().saturating_sub(1)..pieces.len()
+            } else {
+                0..pieces.len()
+            };
+            let target = candidates
+                .filter(|&piece| used[piece] + entry.size <= capacity_of(piece))
+                .min_by_key(|&piece| capacity_of(piece) - used[piece]);
+            match target {
+                Some(piece) => {
+                    pieces[piece].push(position);
+                    used[piece] += entry.size;
+                }
+                None => {
+                    // 现有归档都放不下时新建一个，第一个归档扣除预留空间后可能连一个条目也放不下
+                    if pieces.is_empty() && entry.size > first_capacity {
+                        pieces.push(Vec::new());
+                        used.push(0);
+                    }
+                    pieces.push(vec![position]);
+                    used.push(entry.size);
+                }
+            }
+        }
+        for piece in &mut pieces {
+            piece.sort_unstable();
+        }
+        Ok(pieces)
+    }
+
+    // 第n个(从1开始)输出归档的路径：输入文件名加序号，-b 指定输出目录
+    fn piece_path(&self, number: usize) -> PathBuf {
+        let stem = self
+            .zip_path
+            .file_stem()
+            .map_or_else(|| "split".into(), |stem| stem.to_string_lossy().to_string());
+        let name = format!("{}{}.zip", stem, number);
+        match &self.args.temp_path {
+            Some(dir) => dir.join(name),
+            None => self.zip_path.with_file_name(name),
+        }
+    }
+
+    pub fn run(&self) -> Result<()> {
+        let entries = self.entries()?;
+        if entries.is_empty() {
+            return Err(ZipSplitError::NothingToDo(self.zip_path.display().to_string()).into());
+        }
+        let pieces = self.plan(&entries)?;
+
+        let total: u64 = entries.iter().map(|entry| entry.size).sum();
+        let available = pieces.len() as u64 * self.args.max_size as u64;
+        LogConfig::println(&format!(
+            "{} zip files {} be made ({}% efficiency)",
+            pieces.len(),
+            if self.args.test { "would" } else { "will" },
+            total * 100 / available.max(1)
+        ));
+        if self.args.test {
+            // 只报告拆分方案，不写入任何文件
+            for (number, piece) in pieces.iter().enumerate() {
+                let bytes: u64 = piece.iter().map(|&position| entries[position].size).sum();
+                LogConfig::println(&format!(
+                    "  {}: {} files, {} bytes",
+                    self.piece_path(number + 1).display(),
+                    piece.len(),
+                    bytes + END_OF_CENTRAL_DIR_SIZE as u64
+                ));
+            }
+            return Ok(());
+        }
+
+        if self.args.index {
+            self.write_index(&entries, &pieces)?;
+        }
+        for (number, piece) in pieces.iter().enumerate() {
+            let path = self.piece_path(number + 1);
+            if self.args.pause {
+                pause(&path)?;
+            }
+            LogConfig::println(&format!("creating: {}", path.display()));
+            let mut writer = ZipWriter::new(&path.to_string_lossy())?;
+            for &position in piece {
+                let file = self.archive.by_index_raw(entries[position].index)?;
+                writer.raw_copy_file(&file)?;
+            }
+            writer.finish()?;
+        }
+        Ok(())
+    }
+
+    // 索引文件：每个输出归档一行名称，后面每行一个条目
+    fn index_content(&self, entries: &[SplitEntry], pieces: &[Vec<usize>]) -> String {
+        let mut index = String::new();
+        for (number, piece) in pieces.iter().enumerate() {
+            let piece_path = self.piece_path(number + 1);
+            let piece_name = piece_path.file_name().unwrap_or_default().to_string_lossy();
+            index.push_str(&format!("{}\n", piece_name));
+            for &position in piece {
+                index.push_str(&format!("{}\n", entries[position].name));
+            }
+        }
+        index
+    }
+
+    fn write_index(&self, entries: &[SplitEntry], pieces: &[Vec<usize>]) -> Result<()> {
+        let path = self.piece_path(1).with_file_name(INDEX_FILE_NAME);
+        LogConfig::println(&format!("creating: {}", path.display()));
+        fs::write(path, self.index_content(entries, pieces))?;
+        Ok(())
     }
 }
 
-fn main() {
-    println!("Hello, world!");
+// -p：写入每个归档之前等待用户确认
+fn pause(path: &std::path::Path) -> Result<()> {
+    print!(
+        "Insert a disk for {} and hit return (quit with ^C): ",
+        path.display()
+    );
+    io::stdout().flush()?;
+    let mut line = String::new();
+    io::stdin().lock().read_line(&mut line)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::zip::{CompressionMethod, FileOptions};
+
+    #[test]
+    fn test_plan_and_split() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("in.zip");
+        let mut writer = ZipWriter::new(&path.to_string_lossy())?;
+        for (i, size) in [700, 600, 300, 400].iter().enumerate() {
+            let mut options = FileOptions::new();
+            options.with_compression(CompressionMethod::Stored);
+            writer.start_file(&format!("f{}", i), options)?;
+            writer.write_all(&vec![b'x'; *size])?;
+        }
+        writer.finish()?;
+
+        let mut args = cli::ZipSplitArgs {
+            zipfile: Some(path.clone()),
+            max_size: 1200,
+            ..Default::default()
+        };
+        let splitter = ZipSplitter::new(&args)?;
+        let entries = splitter.entries()?;
+        // f2可以回填到第一个归档，-s 时只能按顺序放入
+        assert_eq!(splitter.plan(&entries)?, vec![vec![0, 2], vec![1, 3]]);
+        args.sequential = true;
+        let sequential = ZipSplitter::new(&args)?;
+        assert_eq!(
+            sequential.plan(&entries)?,
+            vec![vec![0], vec![1, 2], vec![3]]
+        );
+
+        args.sequential = false;
+        let splitter = ZipSplitter::new(&args)?;
+        splitter.run()?;
+        // 条目大小按实际写入的字节数计算
+        for (number, piece) in [[0, 2], [1, 3]].iter().enumerate() {
+            let path = dir.path().join(format!("in{}.zip", number + 1));
+            let expected: u64 = piece.iter().map(|&i| entries[i].size).sum();
+            assert_eq!(
+                fs::metadata(&path)?.len(),
+                expected + END_OF_CENTRAL_DIR_SIZE as u64
+            );
+            ZipArchive::new(&path.to_string_lossy())?;
+        }
+
+        // 按原顺序首次适配需要4个归档，按大小降序最佳适配只需要3个
+        let synthetic: Vec<SplitEntry> = [200, 500, 400, 700, 100, 300, 800]
+            .iter()
+            .enumerate()
+            .map(|(index, &size)| SplitEntry {
+                index,
+                name: format!("f{}", index),
+                size,
+            })
+            .collect();
+        args.max_size = 1000 + END_OF_CENTRAL_DIR_SIZE as u32;
+        let splitter = ZipSplitter::new(&args)?;
+        assert_eq!(
+            splitter.plan(&synthetic)?,
+            vec![vec![0, 6], vec![3, 5], vec![1, 2, 4]]
+        );
+
+        args.max_size = 600;
+        let splitter = ZipSplitter::new(&args)?;
+        let error = splitter.plan(&entries).unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<ZipSplitError>(),
+            Some(ZipSplitError::EntryTooLarge(_))
+        ));
+        Ok(())
+    }
 }